@@ -0,0 +1,107 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use crate::presentation::Presented;
+
+lazy_static! {
+    /// A citation such as `[R1]` or `[R1, R3]`.
+    static ref CITATION: Regex = Regex::new(r"(?i)\s*\[\s*R\s*\d+(?:\s*[,;]\s*R?\s*\d+)*\s*\]").unwrap();
+    static ref NUMBER: Regex = Regex::new(r"\d+").unwrap();
+}
+
+/// A statement of the consensus and the solvers whose responses support it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Claim {
+    pub text: String,
+    /// Tools that wrote the cited responses; empty when the statement cites nothing.
+    pub solvers: Vec<String>,
+    /// 1-based positions of the cited responses in the report's `responses`.
+    pub responses: Vec<usize>,
+}
+
+/// A cited consensus answer, checked against the responses the judge saw.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Attribution {
+    pub claims: Vec<Claim>,
+    /// Citations of responses that do not exist, e.g. `R7` with three responses.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub invalid: Vec<String>,
+    /// The answer with each citation replaced by solver names, e.g. `[gemini, q]`.
+    #[serde(skip)]
+    pub text: String,
+}
+
+impl Attribution {
+    /// Number of statements that cite no valid response.
+    pub fn uncited(&self) -> usize {
+        self.claims.iter().filter(|claim| claim.solvers.is_empty()).count()
+    }
+}
+
+/// Splits a judge answer into statements at its `[R<n>]` citations and resolves each label
+/// through `mapping` to the solver that wrote the response. Labels without a response are
+/// dropped from the text and listed in `invalid`.
+pub fn attribute(answer: &str, mapping: &[Presented]) -> Attribution {
+    let mut attribution = Attribution::default();
+    let mut last = 0;
+    for citation in CITATION.find_iter(answer) {
+        let text = &answer[last..citation.start()];
+        last = citation.end();
+
+        let mut claim = Claim { text: statement(text), solvers: Vec::new(), responses: Vec::new() };
+        for number in NUMBER.find_iter(citation.as_str()) {
+            let label: usize = number.as_str().parse().unwrap_or_default();
+            match mapping.iter().find(|presented| presented.label == label) {
+                Some(presented) => {
+                    if !claim.responses.contains(&presented.response) {
+                        claim.responses.push(presented.response);
+                    }
+                    if !claim.solvers.contains(&presented.solver) {
+                        claim.solvers.push(presented.solver.clone());
+                    }
+                },
+                None => {
+                    let label = format!("R{}", label);
+                    if !attribution.invalid.contains(&label) {
+                        attribution.invalid.push(label);
+                    }
+                },
+            }
+        }
+
+        attribution.text.push_str(text);
+        if !claim.solvers.is_empty() {
+            attribution.text.push_str(&format!(" [{}]", claim.solvers.join(", ")));
+        }
+        match attribution.claims.last_mut() {
+            // Citations in a row, e.g. "[R1] [R2]", belong to the same statement
+            Some(previous) if claim.text.is_empty() => {
+                for solver in claim.solvers {
+                    if !previous.solvers.contains(&solver) {
+                        previous.solvers.push(solver);
+                    }
+                }
+                for response in claim.responses {
+                    if !previous.responses.contains(&response) {
+                        previous.responses.push(response);
+                    }
+                }
+            },
+            _ if claim.text.is_empty() => {},
+            _ => attribution.claims.push(claim),
+        }
+    }
+
+    let rest = &answer[last..];
+    attribution.text.push_str(rest);
+    let rest = statement(rest);
+    if !rest.is_empty() {
+        attribution.claims.push(Claim { text: rest, solvers: Vec::new(), responses: Vec::new() });
+    }
+    attribution
+}
+
+/// The text of one statement, without the punctuation a citation placed before it.
+fn statement(text: &str) -> String {
+    text.trim().trim_start_matches(['.', ',', ';', ':']).trim().to_string()
+}
@@ -0,0 +1,306 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use crate::{Config, Scheduler, RunReport, CliError, Result, ui};
+use crate::{output::OutputFormat, pipeline::{self, RunOptions}};
+use log::info;
+
+/// One prompt of a batch input file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub id: String,
+    pub prompt: String,
+}
+
+/// One line of the results file: the run report of an answered row, or why it failed.
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum RowResult {
+    Answered {
+        id: String,
+        #[serde(flatten)]
+        report: Box<RunReport>,
+    },
+    Failed {
+        id: String,
+        prompt: String,
+        error: String,
+    },
+}
+
+/// What a batch run did, counting the rows resumed from an earlier run as answered.
+#[derive(Serialize, Debug, Default, PartialEq, Eq)]
+pub struct BatchSummary {
+    pub rows: usize,
+    pub answered: usize,
+    /// Ids of the rows that failed, in input order.
+    pub failed: Vec<String>,
+    /// Rows answered by an earlier run and not asked again.
+    pub resumed: usize,
+}
+
+impl BatchSummary {
+    pub fn render(&self, format: OutputFormat) -> String {
+        let failed = if self.failed.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", self.failed.join(", "))
+        };
+        match format {
+            OutputFormat::Json => serde_json::to_string_pretty(self).unwrap_or_else(|e| format!("{{\"error\": \"{}\"}}", e)),
+            OutputFormat::Markdown => format!(
+                "- Rows: {}\n- Answered: {}\n- Failed: {}{}\n- Resumed: {}",
+                self.rows, self.answered, self.failed.len(), failed, self.resumed
+            ),
+            OutputFormat::Text => format!(
+                "📦 {} row(s): {} answered, {} failed{}, {} resumed",
+                self.rows, self.answered, self.failed.len(), failed, self.resumed
+            ),
+        }
+    }
+}
+
+pub struct Batch;
+
+impl Batch {
+    /// Reads the prompts of a batch: a `.csv` file with a `prompt` column, otherwise JSONL with
+    /// one `{"id": ..., "prompt": ...}` object per line. Rows without an id take the number of
+    /// the line they start on, like `ai-co eval` cases.
+    pub fn load(path: &str) -> Result<Vec<Row>> {
+        let content = std::fs::read_to_string(path).map_err(|e| CliError::ConfigError {
+            message: format!("Cannot read batch input {}: {}", path, e)
+        })?;
+        let is_csv = Path::new(path).extension().is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+        let rows = if is_csv { Self::parse_csv(path, &content)? } else { Self::parse_jsonl(path, &content)? };
+
+        let mut ids = HashSet::new();
+        for (line, row) in &rows {
+            if !ids.insert(row.id.as_str()) {
+                return Err(CliError::ConfigError { message: format!("{}:{}: duplicate id '{}'", path, line, row.id) });
+            }
+        }
+        if rows.is_empty() {
+            return Err(CliError::ConfigError { message: format!("Batch input {} has no prompts", path) });
+        }
+        Ok(rows.into_iter().map(|(_, row)| row).collect())
+    }
+
+    fn parse_jsonl(path: &str, content: &str) -> Result<Vec<(usize, Row)>> {
+        let mut rows = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |reason: &str| CliError::ConfigError { message: format!("{}:{}: {}", path, index + 1, reason) };
+            let object: serde_json::Map<String, Value> = serde_json::from_str(line).map_err(|e| invalid(&e.to_string()))?;
+            let prompt = match object.get("prompt") {
+                Some(Value::String(prompt)) => prompt.clone(),
+                Some(_) => return Err(invalid("prompt must be a string")),
+                None => return Err(invalid("missing prompt")),
+            };
+            let id = match object.get("id") {
+                None | Some(Value::Null) => (index + 1).to_string(),
+                Some(Value::String(id)) => id.clone(),
+                Some(Value::Number(id)) => id.to_string(),
+                Some(_) => return Err(invalid("id must be a string or a number")),
+            };
+            rows.push((index + 1, Row { id, prompt }));
+        }
+        Ok(rows)
+    }
+
+    fn parse_csv(path: &str, content: &str) -> Result<Vec<(usize, Row)>> {
+        let records = csv_records(content).map_err(|(line, reason)| CliError::ConfigError {
+            message: format!("{}:{}: {}", path, line, reason)
+        })?;
+        let mut records = records.into_iter();
+        let header = match records.next() {
+            Some((_, header)) => header,
+            None => return Ok(Vec::new()),
+        };
+        let column = |name: &str| header.iter().position(|h| h.trim().eq_ignore_ascii_case(name));
+        let prompt_column = column("prompt").ok_or_else(|| CliError::ConfigError {
+            message: format!("{}:1: the header has no prompt column", path)
+        })?;
+        let id_column = column("id");
+
+        let mut rows = Vec::new();
+        for (line, fields) in records {
+            let field = |i: usize| fields.get(i).cloned().unwrap_or_default();
+            let id = id_column.map(field).filter(|id| !id.trim().is_empty()).unwrap_or_else(|| line.to_string());
+            rows.push((line, Row { id, prompt: field(prompt_column) }));
+        }
+        Ok(rows)
+    }
+
+    /// Reads the results file of an earlier, possibly interrupted, run and returns the ids of
+    /// the rows it answered, with their prompts. Failed rows and lines cut off by the
+    /// interruption are not counted.
+    pub fn load_answered(out: &Path) -> HashMap<String, String> {
+        let content = std::fs::read_to_string(out).unwrap_or_default();
+        let mut answered = HashMap::new();
+        for line in content.lines() {
+            if let Some((id, prompt, failed)) = result_of(line) {
+                if failed {
+                    answered.remove(&id);
+                } else {
+                    answered.insert(id, prompt);
+                }
+            }
+        }
+        answered
+    }
+
+    /// Answers the rows not yet in `out`, at most `concurrency` at a time, appending each result
+    /// as soon as it is known. A failed row is recorded and the batch goes on; at the end `out`
+    /// is rewritten in input order with one line per row.
+    pub async fn run(rows: &[Row], options: &RunOptions, config: &Config, out: &Path, concurrency: usize) -> Result<BatchSummary> {
+        // A row counts as answered only for the same prompt: ids from line numbers move when
+        // rows are inserted
+        let answered = Self::load_answered(out);
+        let pending: Vec<Row> = rows.iter().filter(|row| answered.get(&row.id) != Some(&row.prompt)).cloned().collect();
+        let resumed = rows.len() - pending.len();
+        info!("Answering {} of {} row(s)", pending.len(), rows.len());
+        ui::show_batch_start(pending.len(), resumed);
+
+        let write_error = |e: std::io::Error| CliError::ConfigError {
+            message: format!("Cannot write results file {}: {}", out.display(), e)
+        };
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(out).map_err(write_error)?;
+        // Start on a fresh line after one cut off by an interruption
+        if std::fs::read(out).is_ok_and(|content| content.last().is_some_and(|&byte| byte != b'\n')) {
+            writeln!(file).map_err(write_error)?;
+        }
+        let scheduler = Scheduler::new(config.max_parallel, config.tools.values());
+        let limit = Arc::new(Semaphore::new(concurrency.max(1)));
+        let mut tasks = JoinSet::new();
+        for row in pending {
+            let (options, config, scheduler, limit) = (options.clone(), config.clone(), scheduler.clone(), limit.clone());
+            tasks.spawn(async move {
+                let _permit = limit.acquire_owned().await.ok();
+                match pipeline::answer(&row.prompt, &options, &config, &scheduler).await {
+                    Ok(report) => RowResult::Answered { id: row.id, report: Box::new(report) },
+                    Err(e) => RowResult::Failed { id: row.id, prompt: row.prompt, error: e.to_string() },
+                }
+            });
+        }
+
+        let total = tasks.len();
+        let mut failed = HashSet::new();
+        let mut count = 0;
+        while let Some(result) = tasks.join_next().await {
+            let result = result.map_err(|e| CliError::ConfigError { message: format!("Task join error: {}", e) })?;
+            let line = serde_json::to_string(&result).unwrap_or_default();
+            writeln!(file, "{}", line).and_then(|_| file.flush()).map_err(write_error)?;
+            count += 1;
+            ui::show_batch_row(count, total, &result);
+            if let RowResult::Failed { id, .. } = result {
+                failed.insert(id);
+            }
+        }
+        Self::compact(rows, out)?;
+
+        let failed: Vec<String> = rows.iter().filter(|row| failed.contains(&row.id)).map(|row| row.id.clone()).collect();
+        Ok(BatchSummary { rows: rows.len(), answered: rows.len() - failed.len(), failed, resumed })
+    }
+
+    /// Rewrites `out` with the last result of each row, in input order. Results of rows that
+    /// are no longer in the input are kept after them.
+    fn compact(rows: &[Row], out: &Path) -> Result<()> {
+        let content = std::fs::read_to_string(out).unwrap_or_default();
+        let mut latest: HashMap<String, &str> = HashMap::new();
+        let mut others: Vec<String> = Vec::new();
+        for line in content.lines() {
+            if let Some((id, _, _)) = result_of(line) {
+                if !latest.contains_key(&id) && !rows.iter().any(|row| row.id == id) && !others.contains(&id) {
+                    others.push(id.clone());
+                }
+                latest.insert(id, line);
+            }
+        }
+        let lines: Vec<&str> = rows.iter().map(|row| &row.id).chain(&others).filter_map(|id| latest.get(id).copied()).collect();
+
+        let temporary = out.with_extension("jsonl.tmp");
+        std::fs::write(&temporary, lines.iter().map(|line| format!("{}\n", line)).collect::<String>())
+            .and_then(|_| std::fs::rename(&temporary, out))
+            .map_err(|e| CliError::ConfigError { message: format!("Cannot write results file {}: {}", out.display(), e) })
+    }
+}
+
+/// The id and prompt of a results line and whether the row failed, or `None` for a damaged line.
+fn result_of(line: &str) -> Option<(String, String, bool)> {
+    let object: serde_json::Map<String, Value> = serde_json::from_str(line).ok()?;
+    let id = object.get("id")?.as_str()?.to_string();
+    let prompt = object.get("prompt").and_then(Value::as_str).unwrap_or_default().to_string();
+    Some((id, prompt, object.contains_key("error")))
+}
+
+/// The fields of a CSV record and the line it starts on.
+type Record = (usize, Vec<String>);
+
+/// Splits CSV text into records. Fields may be quoted, with `""` for a quote and line breaks
+/// inside the quotes; blank lines are skipped.
+fn csv_records(content: &str) -> std::result::Result<Vec<Record>, (usize, String)> {
+    let mut records = Vec::new();
+    let mut fields: Vec<String> = Vec::new();
+    let mut field = String::new();
+    let (mut line, mut start) = (1, 1);
+    let (mut quoted, mut was_quoted) = (false, false);
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                },
+                '"' => quoted = false,
+                _ => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    field.push(c);
+                },
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() && !was_quoted => {
+                quoted = true;
+                was_quoted = true;
+            },
+            '"' => return Err((line, "unexpected quote in an unquoted field".to_string())),
+            ',' => {
+                fields.push(std::mem::take(&mut field));
+                was_quoted = false;
+            },
+            '\r' if chars.peek() == Some(&'\n') => {},
+            '\n' => {
+                fields.push(std::mem::take(&mut field));
+                if fields.len() > 1 || was_quoted || !fields[0].is_empty() {
+                    records.push((start, std::mem::take(&mut fields)));
+                }
+                fields.clear();
+                was_quoted = false;
+                line += 1;
+                start = line;
+            },
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return Err((start, "unterminated quoted field".to_string()));
+    }
+    if !fields.is_empty() || was_quoted || !field.is_empty() {
+        fields.push(field);
+        records.push((start, fields));
+    }
+    Ok(records)
+}
@@ -0,0 +1,409 @@
+use std::collections::HashMap;
+use crate::{Config, Validator, constants::{MAX_PROMPT_LENGTH, MIN_INPUT_CHARS}, config::interpolate, layers::merge_values, schema::config_schema, redaction::{RedactionPolicy, BUILTIN_DETECTORS}, privacy::Privacy, consensus::Strategy, weights};
+use toml_edit::{ImDocument, Item, TableLike};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// One problem found in a configuration file.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// Dotted key path the problem refers to, if any.
+    pub key: Option<String>,
+    pub location: Option<Location>,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.location {
+            Some(loc) => write!(f, "{}:{}:{}: {}: {}", loc.file, loc.line, loc.column, self.severity, self.message),
+            None => write!(f, "{}: {}", self.severity, self.message),
+        }
+    }
+}
+
+/// Converts a byte offset into a 1-based line and column.
+pub fn line_col(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map(|l| l.chars().count()).unwrap_or(0) + 1;
+    (line, column)
+}
+
+struct Source {
+    file: String,
+    content: String,
+    document: ImDocument<String>,
+}
+
+impl Source {
+    /// Finds the position of a dotted key path in this file.
+    fn locate(&self, key: &str) -> Option<Location> {
+        let mut table: &dyn TableLike = self.document.as_table();
+        let mut span = None;
+
+        for segment in key.split('.') {
+            let (found_key, item) = table.get_key_value(segment)?;
+            span = found_key.span().or_else(|| item.span()).or(span);
+            match item.as_table_like() {
+                Some(inner) => table = inner,
+                None => break,
+            }
+        }
+
+        span.map(|range| {
+            let (line, column) = line_col(&self.content, range.start);
+            Location { file: self.file.clone(), line, column }
+        })
+    }
+}
+
+pub struct ConfigChecker {
+    sources: Vec<Source>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl ConfigChecker {
+    /// Checks one or more configuration files, given as `(file name, content)` and merged
+    /// in order, the same way layers are merged when the configuration is loaded.
+    pub fn check(files: &[(String, String)]) -> Vec<Diagnostic> {
+        let mut checker = ConfigChecker { sources: Vec::new(), diagnostics: Vec::new() };
+
+        for (file, content) in files {
+            match ImDocument::parse(content.clone()) {
+                Ok(document) => {
+                    let source = Source { file: file.clone(), content: content.clone(), document };
+                    checker.check_unknown_keys(&source);
+                    checker.sources.push(source);
+                },
+                Err(e) => {
+                    let location = e.span().map(|range| {
+                        let (line, column) = line_col(content, range.start);
+                        Location { file: file.clone(), line, column }
+                    });
+                    checker.diagnostics.push(Diagnostic {
+                        severity: Severity::Error,
+                        message: e.message().trim().to_string(),
+                        key: None,
+                        location,
+                    });
+                }
+            }
+        }
+
+        if checker.sources.len() == files.len() {
+            checker.check_semantics();
+        }
+
+        // Located diagnostics in file order first, then the ones without a position
+        checker.diagnostics.sort_by_key(|d| match &d.location {
+            Some(loc) => (0, files.iter().position(|(f, _)| *f == loc.file).unwrap_or(0), loc.line, loc.column),
+            None => (1, 0, 0, 0),
+        });
+        checker.diagnostics
+    }
+
+    fn push(&mut self, severity: Severity, key: &str, message: String) {
+        let location = self.sources.iter().rev().find_map(|source| source.locate(key));
+        self.diagnostics.push(Diagnostic { severity, message, key: Some(key.to_string()), location });
+    }
+
+    fn check_unknown_keys(&mut self, source: &Source) {
+        let schema = config_schema();
+        let mut unknown = Vec::new();
+        walk_schema(source.document.as_item(), &schema, &schema, "", &mut unknown);
+
+        for key in unknown {
+            let location = source.locate(&key);
+            self.diagnostics.push(Diagnostic {
+                severity: Severity::Warning,
+                message: format!("unknown key '{}'", key),
+                key: Some(key),
+                location,
+            });
+        }
+    }
+
+    fn check_semantics(&mut self) {
+        let mut merged = toml::Value::Table(toml::Table::new());
+        for source in &self.sources {
+            match toml::from_str::<toml::Value>(&source.content) {
+                Ok(value) => merge_values(&mut merged, &value),
+                Err(e) => {
+                    self.diagnostics.push(Diagnostic { severity: Severity::Error, message: e.message().to_string(), key: None, location: None });
+                    return;
+                }
+            }
+        }
+
+        let config: Config = match merged.try_into() {
+            Ok(config) => config,
+            Err(e) => {
+                let e: toml::de::Error = e;
+                self.diagnostics.push(Diagnostic { severity: Severity::Error, message: e.message().trim().to_string(), key: None, location: None });
+                return;
+            }
+        };
+
+        if config.tools.is_empty() {
+            self.diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                message: "configuration must contain at least one tool".to_string(),
+                key: None,
+                location: None,
+            });
+        }
+
+        let mut keys: Vec<&String> = config.tools.keys().collect();
+        keys.sort();
+        let mut seen_commands: HashMap<(String, Vec<String>), &String> = HashMap::new();
+
+        for key in keys {
+            let tool = &config.tools[key];
+            for (field, value) in [("name", &tool.name), ("command", &tool.command), ("description", &tool.description)] {
+                if value.trim().is_empty() {
+                    self.push(Severity::Error, &format!("tools.{}.{}", key, field), format!("tool '{}' has an empty {}", key, field));
+                }
+            }
+            if Validator::validate_command(&tool.command).is_err() {
+                self.push(Severity::Error, &format!("tools.{}.command", key), format!("command '{}' of tool '{}' is not in the allowlist", tool.command, key));
+            }
+            if !tool.args.iter().any(|a| a.contains("{prompt}")) {
+                self.push(Severity::Warning, &format!("tools.{}.args", key), format!("args of tool '{}' have no {{prompt}} placeholder, so the question is never passed", key));
+            }
+            if tool.local && tool.privacy == Some(Privacy::Cloud) {
+                self.push(Severity::Warning, &format!("tools.{}.local", key), format!("tool '{}' sets both local = true and privacy = \"cloud\"; privacy wins", key));
+            }
+            if let Some(sandbox) = &tool.sandbox {
+                if sandbox.isolate_network && !tool.is_local() {
+                    self.push(Severity::Error, &format!("tools.{}.sandbox.isolate_network", key), format!("tool '{}' is not local, so it cannot run without network access", key));
+                }
+                if (sandbox.landlock || sandbox.seccomp || sandbox.isolate_network) && !cfg!(target_os = "linux") {
+                    self.push(Severity::Warning, &format!("tools.{}.sandbox", key), format!("the sandbox of tool '{}' uses Linux-only restrictions, so the tool will not run here", key));
+                }
+                if !sandbox.writable.is_empty() && !sandbox.landlock {
+                    self.push(Severity::Warning, &format!("tools.{}.sandbox.writable", key), format!("sandbox.writable of tool '{}' has no effect without landlock = true", key));
+                }
+            }
+            for fallback in &tool.fallback {
+                if fallback == key {
+                    self.push(Severity::Error, &format!("tools.{}.fallback", key), format!("tool '{}' lists itself as a fallback", key));
+                } else if !config.tools.contains_key(fallback) {
+                    self.push(Severity::Error, &format!("tools.{}.fallback", key), format!("tool '{}' falls back to missing tool '{}'", key, fallback));
+                }
+            }
+            match tool.max_input_chars {
+                Some(chars) if chars < MIN_INPUT_CHARS => self.push(Severity::Error, &format!("tools.{}.max_input_chars", key), format!("max_input_chars of tool '{}' must be at least {}", key, MIN_INPUT_CHARS)),
+                Some(chars) if chars > MAX_PROMPT_LENGTH => self.push(Severity::Warning, &format!("tools.{}.max_input_chars", key), format!("max_input_chars of tool '{}' is capped at the prompt limit of {}", key, MAX_PROMPT_LENGTH)),
+                _ => {},
+            }
+            if !tool.weight().is_finite() {
+                self.push(Severity::Error, &format!("tools.{}.weight", key), format!("weight of tool '{}' must be a finite number", key));
+            } else if tool.weight() < 0.0 {
+                self.push(Severity::Error, &format!("tools.{}.weight", key), format!("weight of tool '{}' must not be negative", key));
+            }
+            match tool.hedge_after_secs {
+                Some(0) => self.push(Severity::Error, &format!("tools.{}.hedge_after_secs", key), format!("hedge_after_secs of tool '{}' must be at least 1", key)),
+                Some(_) if tool.fallback.is_empty() => self.push(Severity::Warning, &format!("tools.{}.hedge_after_secs", key), format!("tool '{}' sets hedge_after_secs but has no fallback to start", key)),
+                _ => {},
+            }
+            let mut env_names: Vec<&String> = tool.env.keys().collect();
+            env_names.sort();
+            for name in env_names {
+                if let Err(e) = interpolate(&tool.env[name], &|_| Some(String::new())) {
+                    self.push(Severity::Error, &format!("tools.{}.env.{}", key, name), format!("env {} of tool '{}': {}", name, key, e));
+                }
+            }
+            if !tool.env_passthrough.is_empty() && !tool.env_clear && tool.sandbox.is_none() {
+                self.push(Severity::Warning, &format!("tools.{}.env_passthrough", key), format!("env_passthrough of tool '{}' has no effect unless env_clear = true or a sandbox is set", key));
+            }
+            if let Some(cwd) = tool.working_dir() {
+                if !cwd.is_dir() {
+                    self.push(Severity::Warning, &format!("tools.{}.cwd", key), format!("working directory {} of tool '{}' does not exist", cwd.display(), key));
+                }
+            }
+            if let Some(other) = seen_commands.insert((tool.command.clone(), tool.args.clone()), key) {
+                self.push(Severity::Warning, &format!("tools.{}.command", key), format!("tool '{}' runs exactly the same command as '{}'", key, other));
+            }
+        }
+
+        let mut profiles: Vec<&String> = config.profiles.keys().collect();
+        profiles.sort();
+        for name in profiles {
+            let profile = &config.profiles[name];
+            for (field, tool) in profile.solvers.iter().map(|t| ("solvers", t)).chain(profile.consensus.iter().map(|t| ("consensus", t))) {
+                if !config.tools.contains_key(tool) {
+                    self.push(Severity::Error, &format!("profiles.{}.{}", name, field), format!("profile '{}' references missing tool '{}'", name, tool));
+                }
+            }
+            match profile.judges {
+                Some(0) => self.push(Severity::Error, &format!("profiles.{}.judges", name), format!("profile '{}' needs at least 1 judge", name)),
+                Some(judges) if judges > profile.consensus.len() => self.push(
+                    Severity::Warning,
+                    &format!("profiles.{}.judges", name),
+                    format!("profile '{}' asks for {} judges but lists {} consensus tool(s)", name, judges, profile.consensus.len())
+                ),
+                _ => {},
+            }
+            if matches!(profile.reconcile, Some(Strategy::Concat | Strategy::First)) {
+                self.push(Severity::Error, &format!("profiles.{}.reconcile", name), format!("profile '{}' can only reconcile judges with judge or vote", name));
+            }
+            let mut weighted: Vec<(&String, &f64)> = profile.weights.iter().collect();
+            weighted.sort_by(|a, b| a.0.cmp(b.0));
+            for (tool, weight) in weighted {
+                if !config.tools.contains_key(tool) {
+                    self.push(Severity::Error, &format!("profiles.{}.weights.{}", name, tool), format!("profile '{}' weights missing tool '{}'", name, tool));
+                } else if !weight.is_finite() {
+                    self.push(Severity::Error, &format!("profiles.{}.weights.{}", name, tool), format!("profile '{}' weight of tool '{}' must be a finite number", name, tool));
+                } else if *weight < 0.0 {
+                    self.push(Severity::Error, &format!("profiles.{}.weights.{}", name, tool), format!("profile '{}' gives tool '{}' a negative weight", name, tool));
+                }
+            }
+            if let Some(path) = &profile.weights_from {
+                if let Err(e) = weights::load_scores(path) {
+                    self.push(Severity::Warning, &format!("profiles.{}.weights_from", name), format!("profile '{}': {}", name, e));
+                }
+            }
+        }
+
+        if config.max_parallel == Some(0) {
+            self.push(Severity::Error, "max_parallel", "max_parallel must be at least 1".to_string());
+        }
+        let mut groups: HashMap<&String, Vec<&String>> = HashMap::new();
+        for (key, tool) in &config.tools {
+            if let Some(group) = &tool.exclusive_group {
+                groups.entry(group).or_default().push(key);
+            }
+        }
+        let mut lone: Vec<(&String, &String)> = groups.iter().filter(|(_, tools)| tools.len() == 1).map(|(group, tools)| (*group, tools[0])).collect();
+        lone.sort();
+        for (group, key) in lone {
+            self.push(Severity::Warning, &format!("tools.{}.exclusive_group", key), format!("exclusive group '{}' has only tool '{}', so it limits nothing", group, key));
+        }
+
+        if let Some(default_profile) = &config.default_profile {
+            if !config.profiles.contains_key(default_profile) {
+                self.push(Severity::Error, "default_profile", format!("default profile '{}' is not defined", default_profile));
+            }
+        }
+
+        if let Some(router) = &config.routing.router {
+            if !config.tools.contains_key(router) {
+                self.push(Severity::Error, "routing.router", format!("router references missing tool '{}'", router));
+            }
+        }
+
+        let mut patterns: Vec<&String> = config.redaction.patterns.keys().collect();
+        patterns.sort();
+        for name in patterns {
+            if let Err(e) = regex::Regex::new(&config.redaction.patterns[name]) {
+                let message = e.to_string().lines().last().unwrap_or_default().trim().to_string();
+                self.push(Severity::Error, &format!("redaction.patterns.{}", name), format!("redaction pattern '{}' is not a valid regex: {}", name, message));
+            }
+        }
+        for name in &config.redaction.disabled {
+            if !BUILTIN_DETECTORS.contains(&name.as_str()) {
+                self.push(Severity::Warning, "redaction.disabled", format!("'{}' is not a built-in detector (expected one of {})", name, BUILTIN_DETECTORS.join(", ")));
+            }
+        }
+        for pattern in &config.injection.patterns {
+            if let Err(e) = regex::Regex::new(pattern) {
+                let message = e.to_string().lines().last().unwrap_or_default().trim().to_string();
+                self.push(Severity::Error, "injection.patterns", format!("injection pattern '{}' is not a valid regex: {}", pattern, message));
+            }
+        }
+        for pattern in &config.presentation.identity_patterns {
+            if let Err(e) = regex::Regex::new(pattern) {
+                let message = e.to_string().lines().last().unwrap_or_default().trim().to_string();
+                self.push(Severity::Error, "presentation.identity_patterns", format!("identity pattern '{}' is not a valid regex: {}", pattern, message));
+            }
+        }
+        if config.redaction.policy == RedactionPolicy::LocalOnly && !config.tools.values().any(|t| t.is_local()) {
+            self.push(Severity::Warning, "redaction.policy", "policy is local-only but no tool has privacy = \"local\"".to_string());
+        }
+
+        let mut workflows: Vec<&String> = config.workflows.keys().collect();
+        workflows.sort();
+        for name in workflows {
+            let workflow = &config.workflows[name];
+            for stage in &workflow.stages {
+                for tool in stage.tools.iter().chain(stage.consensus.iter()) {
+                    if !config.tools.contains_key(tool) {
+                        self.push(Severity::Error, &format!("workflows.{}.stages", name), format!("stage '{}.{}' references missing tool '{}'", name, stage.name, tool));
+                    }
+                }
+            }
+            if let Err(e) = workflow.validate(name, &config.tools) {
+                if !matches!(e, crate::CliError::ToolNotFound { .. }) {
+                    self.push(Severity::Error, &format!("workflows.{}", name), e.to_string());
+                }
+            }
+        }
+    }
+}
+
+fn resolve<'a>(schema: &'a serde_json::Value, root: &'a serde_json::Value) -> &'a serde_json::Value {
+    match schema.get("$ref").and_then(|r| r.as_str()) {
+        Some(reference) => reference.strip_prefix("#/definitions/")
+            .and_then(|name| root["definitions"].get(name))
+            .unwrap_or(schema),
+        None => schema,
+    }
+}
+
+/// Collects the dotted paths of keys that the schema does not allow.
+fn walk_schema(item: &Item, schema: &serde_json::Value, root: &serde_json::Value, path: &str, unknown: &mut Vec<String>) {
+    let schema = resolve(schema, root);
+    let join = |key: &str| if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) };
+
+    if let Some(table) = item.as_table_like() {
+        for (key, child) in table.iter() {
+            let child_schema = match schema.get("properties").and_then(|p| p.get(key)) {
+                Some(property) => property,
+                None => match schema.get("additionalProperties") {
+                    Some(serde_json::Value::Bool(false)) => {
+                        unknown.push(join(key));
+                        continue;
+                    },
+                    Some(additional) if additional.is_object() => additional,
+                    _ => continue,
+                },
+            };
+            walk_schema(child, child_schema, root, &join(key), unknown);
+        }
+    } else if let Some(array) = item.as_array_of_tables() {
+        if let Some(items) = schema.get("items") {
+            for table in array.iter() {
+                for (key, child) in table.iter() {
+                    let items = resolve(items, root);
+                    match items.get("properties").and_then(|p| p.get(key)) {
+                        Some(property) => walk_schema(child, property, root, &join(key), unknown),
+                        None if items.get("additionalProperties") == Some(&serde_json::Value::Bool(false)) => unknown.push(join(key)),
+                        None => {},
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,23 +1,159 @@
 use serde::Deserialize;
 use std::collections::HashMap;
-use crate::{CliError, Result, Validator};
-use log::info;
+use std::path::{Path, PathBuf};
+use crate::{CliError, Result, Validator, constants::{MAX_PROMPT_LENGTH, MIN_INPUT_CHARS}, workflow::WorkflowConfig, routing::RoutingConfig, profile::ProfileConfig, layers::LayeredConfig, redaction::RedactionConfig, privacy::Privacy, injection::InjectionConfig, presentation::PresentationConfig, sandbox::SandboxConfig};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Clone, Debug, Default)]
 pub struct Config {
     pub tools: HashMap<String, ToolConfig>,
+    #[serde(default)]
+    pub workflows: HashMap<String, WorkflowConfig>,
+    #[serde(default)]
+    pub routing: RoutingConfig,
+    #[serde(default)]
+    pub profiles: HashMap<String, ProfileConfig>,
+    /// Profile used when `--profile` is not given.
+    pub default_profile: Option<String>,
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+    #[serde(default)]
+    pub injection: InjectionConfig,
+    #[serde(default)]
+    pub presentation: PresentationConfig,
+    /// Most tools run at once; unlimited when unset.
+    pub max_parallel: Option<usize>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Clone, Debug, Default)]
 pub struct ToolConfig {
     pub name: String,
     pub command: String,
     pub args: Vec<String>,
     pub description: String,
+    /// Capability tags (e.g. "cloud", "code", "privacy") used by `--auto` routing.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Overrides `TOOL_TIMEOUT_SECS` for this tool.
+    pub timeout_secs: Option<u64>,
+    /// Arguments that print the tool's version, used by `ai-co doctor` (default `["--version"]`).
+    pub version_args: Option<Vec<String>>,
+    /// Whether prompts leave the machine; see [`ToolConfig::is_local`].
+    pub privacy: Option<Privacy>,
+    /// Shorthand for `privacy = "local"`.
+    #[serde(default)]
+    pub local: bool,
+    /// Runs the tool in a process sandbox; see [`SandboxConfig`].
+    pub sandbox: Option<SandboxConfig>,
+    /// Variables set for the tool; values may use `${VAR}` and `${VAR:-default}`.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Variables kept from the parent environment when it is cleared.
+    #[serde(default)]
+    pub env_passthrough: Vec<String>,
+    /// Start from an empty environment instead of inheriting this process's.
+    #[serde(default)]
+    pub env_clear: bool,
+    /// Working directory; `~/` is expanded.
+    pub cwd: Option<String>,
+    /// Tools in the same group run one at a time, e.g. models sharing one Ollama.
+    pub exclusive_group: Option<String>,
+    /// Tools that answer in this tool's place when it is unavailable, fails or hits an auth error.
+    #[serde(default)]
+    pub fallback: Vec<String>,
+    /// Starts the first fallback alongside this tool when it is still running after this long.
+    pub hedge_after_secs: Option<u64>,
+    /// Longest prompt this tool accepts as a consensus tool; longer inputs are condensed first.
+    pub max_input_chars: Option<usize>,
+    /// How much this tool's answers count in a vote and for the judge (default 1).
+    pub weight: Option<f64>,
+}
+
+/// Expands a leading `~/` to the home directory.
+pub(crate) fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn is_env_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['=', '\0'])
+}
+
+/// Expands `${VAR}` and `${VAR:-default}` in `value` using `lookup`; `$$` is a literal `$`.
+pub fn interpolate(value: &str, lookup: &dyn Fn(&str) -> Option<String>) -> std::result::Result<String, String> {
+    let mut result = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find('$') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        if let Some(tail) = after.strip_prefix('$') {
+            result.push('$');
+            rest = tail;
+        } else if let Some(tail) = after.strip_prefix('{') {
+            let end = tail.find('}').ok_or_else(|| format!("unterminated '${{' in '{}'", value))?;
+            let (name, default) = match tail[..end].split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (&tail[..end], None),
+            };
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') || name.starts_with(|c: char| c.is_ascii_digit()) {
+                return Err(format!("invalid variable name '{}'", name));
+            }
+            match lookup(name).filter(|v| !v.is_empty() || default.is_none()).or_else(|| default.map(|d| d.to_string())) {
+                Some(v) => result.push_str(&v),
+                None => return Err(format!("variable '{}' is not set", name)),
+            }
+            rest = &tail[end + 1..];
+        } else {
+            result.push('$');
+            rest = after;
+        }
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+impl ToolConfig {
+    /// True when the tool runs entirely on this machine, so sensitive prompts may be sent to it.
+    pub fn is_local(&self) -> bool {
+        match self.privacy {
+            Some(privacy) => privacy == Privacy::Local,
+            None => self.local,
+        }
+    }
+
+    /// The `env` table with variables interpolated from `lookup`, sorted by name.
+    pub fn environment(&self, lookup: &dyn Fn(&str) -> Option<String>) -> Result<Vec<(String, String)>> {
+        let mut names: Vec<&String> = self.env.keys().collect();
+        names.sort();
+        names.into_iter()
+            .map(|name| {
+                interpolate(&self.env[name], lookup)
+                    .map(|value| (name.clone(), value))
+                    .map_err(|e| CliError::ConfigError { message: format!("Tool '{}' env {}: {}", self.name, name, e) })
+            })
+            .collect()
+    }
+
+    /// The configured working directory, with `~/` expanded.
+    pub fn working_dir(&self) -> Option<PathBuf> {
+        self.cwd.as_deref().map(expand_home)
+    }
+
+    /// Longest consensus prompt to send this tool: `max_input_chars`, capped by `MAX_PROMPT_LENGTH`.
+    pub fn input_budget(&self) -> usize {
+        self.max_input_chars.unwrap_or(MAX_PROMPT_LENGTH).min(MAX_PROMPT_LENGTH)
+    }
+
+    /// The configured `weight`, 1 when unset.
+    pub fn weight(&self) -> f64 {
+        self.weight.unwrap_or(1.0)
+    }
 }
 
 impl Config {
-    /// Loads configuration from a TOML file with security validation.
+    /// Loads configuration with security validation, merging every available layer:
+    /// system defaults, user config, project `.ai-consensus.toml`, the given file and env vars.
     /// 
     /// # Examples
     /// 
@@ -28,44 +164,7 @@ impl Config {
     /// assert!(!config.tools.is_empty());
     /// ```
     pub fn load(config_path: &str) -> Result<Self> {
-        let home_dir = std::env::var("HOME").unwrap_or_default();
-        let mut paths_to_try = vec![
-            format!("{}/.config/ai-consensus-cli/config.toml", home_dir),
-            config_path.to_string(),
-        ];
-        
-        if let Ok(override_path) = std::env::var("AI_CONSENSUS_CONFIG") {
-            paths_to_try.insert(0, override_path);
-        }
-        
-        // Add binary location if different from config_path
-        if let Ok(exe) = std::env::current_exe() {
-            if let Some(parent) = exe.parent() {
-                if let Some(bin_path) = parent.join("config.toml").to_str() {
-                    paths_to_try.push(bin_path.to_string());
-                }
-            }
-        }
-        
-        for path in &paths_to_try {
-            // Validate config path for security
-            if let Ok(validated_path) = Validator::validate_config_path(&path) {
-                if let Ok(config_content) = std::fs::read_to_string(&validated_path) {
-                    let config: Config = toml::from_str(&config_content)
-                        .map_err(|e| CliError::ConfigError { 
-                            message: format!("Invalid config format: {}", e)
-                        })?;
-                    
-                    config.validate()?;
-                    info!("Loaded configuration from {} with {} tools", path, config.tools.len());
-                    return Ok(config);
-                }
-            }
-        }
-        
-        Err(CliError::ConfigError { 
-            message: format!("Could not read config file: {} (tried: ~/.config/ai-consensus-cli/config.toml, current dir, binary location)", config_path)
-        })
+        LayeredConfig::load(config_path).map(|layered| layered.config)
     }
 
     pub fn validate(&self) -> Result<()> {
@@ -94,22 +193,150 @@ impl Config {
             
             // Validate command is allowed
             Validator::validate_command(&tool.command)?;
+            
+            if let Some(sandbox) = &tool.sandbox {
+                sandbox.validate(key, tool)?;
+            }
+            
+            for name in tool.env.keys().chain(&tool.env_passthrough) {
+                if !is_env_name(name) {
+                    return Err(CliError::ConfigError { 
+                        message: format!("Tool '{}' has an invalid environment variable name '{}'", key, name)
+                    });
+                }
+            }
+            for fallback in &tool.fallback {
+                if fallback == key {
+                    return Err(CliError::ConfigError { 
+                        message: format!("Tool '{}' lists itself as a fallback", key)
+                    });
+                }
+                if !self.tools.contains_key(fallback) {
+                    return Err(CliError::ConfigError { 
+                        message: format!("Tool '{}' falls back to unknown tool '{}'", key, fallback)
+                    });
+                }
+            }
+            if tool.max_input_chars.is_some_and(|chars| chars < MIN_INPUT_CHARS) {
+                return Err(CliError::ConfigError { 
+                    message: format!("Tool '{}' has max_input_chars below {}", key, MIN_INPUT_CHARS)
+                });
+            }
+            if tool.hedge_after_secs == Some(0) {
+                return Err(CliError::ConfigError { 
+                    message: format!("Tool '{}' has hedge_after_secs = 0; use at least 1", key)
+                });
+            }
+            if !tool.weight().is_finite() {
+                return Err(CliError::ConfigError { 
+                    message: format!("Tool '{}' weight must be a finite number", key)
+                });
+            }
+            if tool.weight() < 0.0 {
+                return Err(CliError::ConfigError { 
+                    message: format!("Tool '{}' has a negative weight", key)
+                });
+            }
+            
+            // Only the syntax is checked here; unset variables are reported when the tool runs
+            for (name, value) in &tool.env {
+                interpolate(value, &|_| Some(String::new())).map_err(|e| CliError::ConfigError { 
+                    message: format!("Tool '{}' env {}: {}", key, name, e)
+                })?;
+            }
+        }
+        
+        for (name, workflow) in &self.workflows {
+            workflow.validate(name, &self.tools)?;
+        }
+        
+        for (name, profile) in &self.profiles {
+            profile.validate(name, &self.tools)?;
+        }
+        
+        if let Some(default_profile) = &self.default_profile {
+            if !self.profiles.contains_key(default_profile) {
+                return Err(CliError::ConfigError { 
+                    message: format!("Default profile '{}' is not defined", default_profile)
+                });
+            }
         }
         
+        if let Some(router) = &self.routing.router {
+            if !self.tools.contains_key(router) {
+                return Err(CliError::ToolNotFound { tool: router.clone() });
+            }
+        }
+        
+        if self.max_parallel == Some(0) {
+            return Err(CliError::ConfigError { 
+                message: "max_parallel must be at least 1".to_string()
+            });
+        }
+        
+        self.redaction.validate()?;
+        self.injection.validate()?;
+        self.presentation.validate()?;
+        
         Ok(())
     }
 
+    /// Returns the named profile, or the `default_profile` when no name is given.
+    /// An empty profile is returned when neither is set.
+    pub fn resolve_profile(&self, name: Option<&str>) -> Result<ProfileConfig> {
+        match name.or(self.default_profile.as_deref()) {
+            Some(name) => self.profiles.get(name).cloned().ok_or_else(|| CliError::ConfigError { 
+                message: format!("Profile '{}' not found in configuration", name)
+            }),
+            None => Ok(ProfileConfig::default()),
+        }
+    }
+
+    /// Applies a run-wide timeout to every tool.
+    pub fn set_timeout(&mut self, timeout_secs: u64) {
+        for tool in self.tools.values_mut() {
+            tool.timeout_secs = Some(timeout_secs);
+        }
+    }
+
     pub fn generate_help_text(&self) -> String {
         let mut help = String::from("Orchestrate multiple AI CLIs with consensus functionality.\n\nAvailable AI Tools:\n");
         
         for (key, tool) in &self.tools {
-            help.push_str(&format!("• {:<12} - {}: {}\n", key, tool.name, tool.description));
+            help.push_str(&format!("• {:<12} - {}: {}", key, tool.name, tool.description));
+            if !tool.tags.is_empty() {
+                help.push_str(&format!(" [{}]", tool.tags.join(", ")));
+            }
+            help.push('\n');
+        }
+        
+        if !self.workflows.is_empty() {
+            help.push_str("\nWorkflows:\n");
+            let mut names: Vec<_> = self.workflows.keys().collect();
+            names.sort();
+            for name in names {
+                help.push_str(&format!("• {:<12} - {}\n", name, self.workflows[name].description));
+            }
+        }
+        
+        if !self.profiles.is_empty() {
+            help.push_str("\nProfiles:\n");
+            let mut names: Vec<_> = self.profiles.keys().collect();
+            names.sort();
+            for name in names {
+                let profile = &self.profiles[name];
+                let marker = if self.default_profile.as_deref() == Some(name.as_str()) { " (default)" } else { "" };
+                help.push_str(&format!("• {:<12} - {}{}\n", name, profile.description, marker));
+            }
         }
         
         help.push_str("\nExamples:\n");
         help.push_str("  ai-co -s q,gemini -c claude -p \"Explain microservices architecture\"\n");
         help.push_str("  ai-co -s q,ollama -c claude -p \"Latest AI developments\"\n");
-        help.push_str("  ai-co -s q,gemini,claude,ollama -c q -p \"Your question here\"");
+        help.push_str("  ai-co -s q,gemini,claude,ollama -c q -p \"Your question here\"\n");
+        help.push_str("  ai-co --auto -c claude -p \"How do I size an AWS Lambda function?\"\n");
+        help.push_str("  ai-co --profile quick -p \"Your question here\"\n");
+        help.push_str("  ai-co run --workflow review -p \"Your question here\"");
         
         help
     }
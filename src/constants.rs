@@ -1,13 +1,43 @@
 pub const TOOL_TIMEOUT_SECS: u64 = 60;
 pub const MAX_PROMPT_LENGTH: usize = 50000;
+/// Smallest `max_input_chars` a tool may set.
+pub const MIN_INPUT_CHARS: usize = 2000;
+/// Shortest a response is condensed to before neighbouring responses are merged instead.
+pub const MIN_CONDENSED_CHARS: usize = 200;
 pub const MEMORY_LIMIT_MB: u64 = 512;
 pub const CPU_LIMIT_SECS: u64 = 60;
 
+/// Model names stripped from self-identifying phrases ("As Claude, ...") before judging.
+pub const IDENTITY_MODELS: &[&str] = &[
+    "claude", "gemini", "chatgpt", r"gpt-?\d(?:\.\d)?o?", r"llama ?\d*(?:\.\d)?", "code ?llama",
+    "mistral", "amazon q", "q developer", "codex", "copilot",
+];
+
+/// Vendors named in self-identifying phrases ("... trained by Google").
+pub const IDENTITY_VENDORS: &[&str] = &[
+    "anthropic", "google(?: deepmind)?", "openai", "meta", "amazon(?: web services)?", "aws", "mistral ai", "microsoft", "github",
+];
+
+/// Environment variables every sandboxed tool keeps.
+pub const SANDBOX_BASE_ENV: &[&str] = &["PATH", "HOME", "USER", "LOGNAME", "LANG", "LC_ALL", "LC_CTYPE", "TERM", "TZ"];
+
 pub const ALLOWED_COMMANDS: &[&str] = &[
     "q", "gemini", "claude", "openai", "ollama", 
     "codex", "mistral"
 ];
 
+/// Case-insensitive regexes for text that tries to instruct the consensus tool.
+pub const INJECTION_PATTERNS: &[&str] = &[
+    r"\b(ignore|disregard|forget)\b.{0,30}\b(previous|prior|above|earlier|all)\b.{0,20}\b(instructions?|prompts?|rules|responses?)\b",
+    r"\byou are now\b",
+    r"\bnew (instructions|task|rules)\s*:",
+    r"\b(system|developer) (prompt|message|instructions?)\b",
+    r"\b(as|to) the (judge|consensus|evaluator|grader)\b",
+    r"\b(respond|reply|answer|output) (only|exactly|solely) with\b",
+    r"\bdo not (mention|include|consider) (the )?other (responses?|answers?)\b",
+    r"<\|im_(start|end)\|>|\[/?INST\]|</?(system|assistant)>",
+];
+
 pub const AUTH_ERROR_PATTERNS: &[&str] = &[
     "invalid api key",
     "api_key client option must be set", 
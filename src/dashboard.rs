@@ -0,0 +1,418 @@
+//! The `--tui` dashboard: a live table of the tools of a run and a consensus panel.
+//!
+//! [`Dashboard`] holds what is known about the run and turns key presses into [`Action`]s;
+//! [`run`] draws it and carries the actions out until the user closes it.
+
+use std::io::Write;
+use std::time::{Duration, Instant};
+use crossterm::event::{self, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::{Frame, layout::{Constraint, Layout}, style::{Style, Stylize}, text::{Line, Span, Text}};
+use ratatui::widgets::{Block, Paragraph, Row, Table, TableState, Wrap};
+use tokio::sync::{mpsc::UnboundedReceiver, oneshot};
+use crate::{Validator, diff::{self, Change}, progress::{self, AttemptId, Event}};
+
+/// How far the tail of a tool's output is searched for the preview.
+const PREVIEW_CHARS: usize = 2000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Queued,
+    Running,
+    Answered,
+    Failed(String),
+    Cancelled,
+}
+
+/// One attempt of the run: a solver or a tool of its fallback chain.
+#[derive(Debug, Clone)]
+pub struct SolverRow {
+    pub id: AttemptId,
+    /// The solver the tool answers for.
+    pub solver: String,
+    pub tool: String,
+    pub status: Status,
+    started: Option<Instant>,
+    elapsed: Option<Duration>,
+    /// Everything the tool printed so far; its response once it answered.
+    pub output: String,
+    cancelling: bool,
+}
+
+impl SolverRow {
+    fn elapsed(&self) -> Option<Duration> {
+        self.elapsed.or_else(|| self.started.map(|started| started.elapsed()))
+    }
+
+    /// The last line the tool printed, or why it failed.
+    fn preview(&self) -> String {
+        if let Status::Failed(reason) = &self.status {
+            return reason.clone();
+        }
+        let tail = match self.output.char_indices().rev().nth(PREVIEW_CHARS) {
+            Some((start, _)) => &self.output[start..],
+            None => &self.output,
+        };
+        Validator::strip_ansi_codes(tail).lines().rev().map(str::trim).find(|line| !line.is_empty()).unwrap_or_default().to_string()
+    }
+}
+
+/// What the dashboard shows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum View {
+    Table,
+    /// The full output of a row.
+    Response { row: usize, scroll: u16 },
+    /// The line diff from the output of row `from` to that of row `to`.
+    Diff { from: usize, to: usize, scroll: u16 },
+}
+
+/// What a key press asks for beyond redrawing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    None,
+    Cancel(AttemptId),
+    Copy(String),
+    /// Close the dashboard once the run is over.
+    Quit,
+    /// Stop the run.
+    Abort,
+}
+
+/// How the dashboard was closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    Closed,
+    Aborted,
+}
+
+#[derive(Debug, Default)]
+enum Consensus {
+    #[default]
+    Waiting,
+    Running { strategy: String },
+    Done { tool: Option<String>, text: String },
+}
+
+#[derive(Debug)]
+pub struct Dashboard {
+    rows: Vec<SolverRow>,
+    selected: usize,
+    view: View,
+    /// The row picked as the old side of a diff.
+    marked: Option<usize>,
+    consensus: Consensus,
+    /// `None` while the run goes on, then its error, if any.
+    finished: Option<Result<(), String>>,
+    notice: Option<String>,
+}
+
+impl Default for Dashboard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Dashboard {
+    pub fn new() -> Self {
+        Dashboard { rows: Vec::new(), selected: 0, view: View::Table, marked: None, consensus: Consensus::Waiting, finished: None, notice: None }
+    }
+
+    pub fn rows(&self) -> &[SolverRow] {
+        &self.rows
+    }
+
+    pub fn view(&self) -> &View {
+        &self.view
+    }
+
+    pub fn notice(&self) -> Option<&str> {
+        self.notice.as_deref()
+    }
+
+    fn row(&mut self, id: AttemptId) -> Option<&mut SolverRow> {
+        self.rows.iter_mut().find(|row| row.id == id)
+    }
+
+    pub fn apply(&mut self, event: Event) {
+        match event {
+            Event::Queued { id, solver, tool } => {
+                if self.row(id).is_none() {
+                    self.rows.push(SolverRow { id, solver, tool, status: Status::Queued, started: None, elapsed: None, output: String::new(), cancelling: false });
+                }
+            },
+            Event::Started { id } => {
+                if let Some(row) = self.row(id) {
+                    row.status = Status::Running;
+                    row.started = Some(Instant::now());
+                }
+            },
+            Event::Output { id, text } => {
+                if let Some(row) = self.row(id) {
+                    row.output.push_str(&text);
+                }
+            },
+            Event::Finished { id, result } => {
+                if let Some(row) = self.row(id) {
+                    row.elapsed = Some(row.started.map(|started| started.elapsed()).unwrap_or_default());
+                    match result {
+                        Ok(response) => {
+                            row.status = Status::Answered;
+                            row.output = response;
+                        },
+                        Err(_) if row.cancelling => row.status = Status::Cancelled,
+                        Err(reason) => row.status = Status::Failed(reason),
+                    }
+                }
+            },
+            Event::ConsensusStarted { strategy } => self.consensus = Consensus::Running { strategy },
+            Event::Consensus { tool, text } => self.consensus = Consensus::Done { tool, text },
+        }
+    }
+
+    /// Records the end of the run, with its error if it failed.
+    pub fn finish(&mut self, result: Result<(), String>) {
+        self.finished = Some(result);
+    }
+
+    pub fn key(&mut self, key: KeyEvent) -> Action {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Action::Abort;
+        }
+        self.notice = None;
+        match self.view.clone() {
+            View::Table => self.table_key(key.code),
+            View::Response { row, scroll } => {
+                self.view = match Self::scrolled(key.code, scroll) {
+                    Some(scroll) => View::Response { row, scroll },
+                    None => View::Table,
+                };
+                Action::None
+            },
+            View::Diff { from, to, scroll } => {
+                self.view = match Self::scrolled(key.code, scroll) {
+                    Some(scroll) => View::Diff { from, to, scroll },
+                    None => View::Table,
+                };
+                Action::None
+            },
+        }
+    }
+
+    /// The scroll position after `code`, or `None` when it closes the view.
+    fn scrolled(code: KeyCode, scroll: u16) -> Option<u16> {
+        match code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Enter => None,
+            KeyCode::Down | KeyCode::Char('j') => Some(scroll.saturating_add(1)),
+            KeyCode::Up | KeyCode::Char('k') => Some(scroll.saturating_sub(1)),
+            KeyCode::PageDown | KeyCode::Char(' ') => Some(scroll.saturating_add(10)),
+            KeyCode::PageUp => Some(scroll.saturating_sub(10)),
+            KeyCode::Home | KeyCode::Char('g') => Some(0),
+            _ => Some(scroll),
+        }
+    }
+
+    fn table_key(&mut self, code: KeyCode) -> Action {
+        match code {
+            KeyCode::Down | KeyCode::Char('j') => self.selected = (self.selected + 1).min(self.rows.len().saturating_sub(1)),
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Char('c') => {
+                let Some(row) = self.rows.get_mut(self.selected) else {
+                    return Action::None;
+                };
+                if !matches!(row.status, Status::Queued | Status::Running) {
+                    self.notice = Some(format!("{} is not running", row.tool));
+                    return Action::None;
+                }
+                row.cancelling = true;
+                self.notice = Some(format!("Cancelling {}", row.tool));
+                return Action::Cancel(row.id);
+            },
+            KeyCode::Enter | KeyCode::Char('v') if self.selected < self.rows.len() => {
+                self.view = View::Response { row: self.selected, scroll: 0 };
+            },
+            KeyCode::Char('d') => match self.marked.take() {
+                None if self.selected < self.rows.len() => {
+                    self.marked = Some(self.selected);
+                    self.notice = Some(format!("Diff from {}: select another response and press d", self.rows[self.selected].tool));
+                },
+                Some(from) if from == self.selected => self.notice = Some("Diff cancelled".to_string()),
+                Some(from) => self.view = View::Diff { from, to: self.selected, scroll: 0 },
+                None => {},
+            },
+            KeyCode::Char('y') => match &self.consensus {
+                Consensus::Done { text, .. } => {
+                    self.notice = Some("Copied the consensus to the clipboard".to_string());
+                    return Action::Copy(text.clone());
+                },
+                _ => self.notice = Some("There is no consensus yet".to_string()),
+            },
+            KeyCode::Esc | KeyCode::Char('q') => {
+                if self.finished.is_some() {
+                    return Action::Quit;
+                }
+                self.notice = Some("The run is still going: c cancels a tool, Ctrl-C stops the run".to_string());
+            },
+            _ => {},
+        }
+        Action::None
+    }
+
+    pub fn draw(&self, frame: &mut Frame) {
+        match &self.view {
+            View::Table => self.draw_table(frame),
+            View::Response { row, scroll } => {
+                let row = &self.rows[*row];
+                let title = format!(" {} ({}), {} ", row.tool, status_label(&row.status), size(row.output.len()));
+                let text = Validator::strip_ansi_codes(&row.output);
+                frame.render_widget(
+                    Paragraph::new(text).wrap(Wrap { trim: false }).scroll((*scroll, 0)).block(Block::bordered().title(title).title_bottom(" ↑↓ scroll · Esc back ")),
+                    frame.area(),
+                );
+            },
+            View::Diff { from, to, scroll } => {
+                let (old, new) = (&self.rows[*from], &self.rows[*to]);
+                let (old_text, new_text) = (Validator::strip_ansi_codes(&old.output), Validator::strip_ansi_codes(&new.output));
+                let lines: Vec<Line> = diff::lines(&old_text, &new_text).into_iter()
+                    .map(|(change, line)| match change {
+                        Change::Same => Line::raw(format!("  {}", line)),
+                        Change::Removed => Line::styled(format!("- {}", line), Style::new().red()),
+                        Change::Added => Line::styled(format!("+ {}", line), Style::new().green()),
+                    })
+                    .collect();
+                let title = format!(" - {}  + {} ", old.tool, new.tool);
+                frame.render_widget(
+                    Paragraph::new(lines).wrap(Wrap { trim: false }).scroll((*scroll, 0)).block(Block::bordered().title(title).title_bottom(" ↑↓ scroll · Esc back ")),
+                    frame.area(),
+                );
+            },
+        }
+    }
+
+    fn draw_table(&self, frame: &mut Frame) {
+        let [table_area, consensus_area, help_area] = Layout::vertical([
+            Constraint::Min(3 + self.rows.len().min(8) as u16),
+            Constraint::Percentage(40),
+            Constraint::Length(1),
+        ]).areas(frame.area());
+
+        let rows = self.rows.iter().enumerate().map(|(i, row)| {
+            let solver = if self.marked == Some(i) { format!("» {}", row.solver) } else { row.solver.clone() };
+            let elapsed = row.elapsed().map(|elapsed| format!("{:.1}s", elapsed.as_secs_f32())).unwrap_or_else(|| "-".to_string());
+            let status = Span::styled(status_label(&row.status), match row.status {
+                Status::Answered => Style::new().green(),
+                Status::Failed(_) | Status::Cancelled => Style::new().red(),
+                Status::Queued => Style::new().dim(),
+                Status::Running => Style::new().yellow(),
+            });
+            Row::new(vec![Line::raw(solver), Line::raw(row.tool.clone()), Line::from(status), Line::raw(elapsed), Line::raw(size(row.output.len())), Line::raw(row.preview()).dim()])
+        });
+        let widths = [Constraint::Length(12), Constraint::Length(12), Constraint::Length(12), Constraint::Length(8), Constraint::Length(9), Constraint::Fill(1)];
+        let table = Table::new(rows, widths)
+            .header(Row::new(["Solver", "Tool", "Status", "Elapsed", "Output", "Preview"]).bold())
+            .row_highlight_style(Style::new().reversed())
+            .block(Block::bordered().title(" Solvers "));
+        let mut state = TableState::default().with_selected((!self.rows.is_empty()).then_some(self.selected));
+        frame.render_stateful_widget(table, table_area, &mut state);
+
+        let (title, text) = match (&self.consensus, &self.finished) {
+            (Consensus::Done { tool, text }, _) => {
+                let title = match tool {
+                    Some(tool) => format!(" Consensus by {} ", tool),
+                    None => " Consensus ".to_string(),
+                };
+                (title, Text::raw(text.clone()))
+            },
+            (_, Some(Err(error))) => (" Consensus ".to_string(), Text::styled(error.clone(), Style::new().red())),
+            (Consensus::Running { strategy }, _) => (" Consensus ".to_string(), Text::raw(format!("Combining the responses ({} strategy)...", strategy)).dim()),
+            (Consensus::Waiting, _) => (" Consensus ".to_string(), Text::raw("Waiting for the solvers...").dim()),
+        };
+        frame.render_widget(Paragraph::new(text).wrap(Wrap { trim: false }).block(Block::bordered().title(title)), consensus_area);
+
+        let help = match (&self.notice, &self.finished) {
+            (Some(notice), _) => Line::raw(notice.clone()).yellow(),
+            (None, Some(_)) => Line::raw("Done · ↑↓ select · ⏎ view · d diff · y copy consensus · q quit").dim(),
+            (None, None) => Line::raw("↑↓ select · c cancel · ⏎ view · d diff · y copy consensus · Ctrl-C stop").dim(),
+        };
+        frame.render_widget(help, help_area);
+    }
+}
+
+fn status_label(status: &Status) -> String {
+    match status {
+        Status::Queued => "⏳ queued",
+        Status::Running => "🔄 running",
+        Status::Answered => "✅ answered",
+        Status::Failed(_) => "❌ failed",
+        Status::Cancelled => "⛔ cancelled",
+    }.to_string()
+}
+
+fn size(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    }
+}
+
+/// Shows the dashboard until the user closes it, applying `events` as they arrive and the
+/// end of the run once `finished` reports it. The terminal is restored however it ends.
+pub fn run(mut events: UnboundedReceiver<Event>, mut finished: oneshot::Receiver<Result<(), String>>) -> std::io::Result<Exit> {
+    let mut terminal = ratatui::try_init()?;
+    let mut dashboard = Dashboard::new();
+    let result = (|| loop {
+        while let Ok(event) = events.try_recv() {
+            dashboard.apply(event);
+        }
+        if dashboard.finished.is_none() {
+            match finished.try_recv() {
+                Ok(result) => dashboard.finish(result),
+                Err(oneshot::error::TryRecvError::Closed) => dashboard.finish(Err("The run stopped unexpectedly".to_string())),
+                Err(oneshot::error::TryRecvError::Empty) => {},
+            }
+        }
+        terminal.draw(|frame| dashboard.draw(frame))?;
+
+        if !event::poll(Duration::from_millis(100))? {
+            continue;
+        }
+        let event::Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match dashboard.key(key) {
+            Action::None => {},
+            Action::Cancel(id) => progress::cancel(id),
+            Action::Copy(text) => copy(&text)?,
+            Action::Quit => return Ok(Exit::Closed),
+            Action::Abort => return Ok(Exit::Aborted),
+        }
+    })();
+    ratatui::restore();
+    result
+}
+
+/// Puts `text` on the clipboard with the OSC 52 escape sequence, which also works over SSH.
+fn copy(text: &str) -> std::io::Result<()> {
+    let mut stdout = std::io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+    stdout.flush()
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, byte)| group | (*byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
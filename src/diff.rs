@@ -0,0 +1,314 @@
+//! Line and word diffs between responses, as unified-diff text or coloured for a terminal.
+
+use std::io::IsTerminal;
+use crate::{CliError, Result};
+
+/// Lines kept around each change.
+const CONTEXT: usize = 3;
+/// Largest table the LCS may fill; bigger inputs are shown as removed and re-added whole.
+const MAX_CELLS: usize = 4_000_000;
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const CYAN: &str = "\x1b[36m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// Whether a piece of text is in both versions, only the old one or only the new one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Same,
+    Removed,
+    Added,
+}
+
+/// What a diff compares: whole lines, or the words within changed lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Granularity {
+    #[default]
+    Lines,
+    Words,
+}
+
+impl std::str::FromStr for Granularity {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "lines" | "line" => Ok(Granularity::Lines),
+            "words" | "word" => Ok(Granularity::Words),
+            _ => Err(CliError::ConfigError {
+                message: format!("Unknown diff granularity '{}' (expected lines or words)", s)
+            }),
+        }
+    }
+}
+
+/// How a diff is printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// Unified-diff text, for files, pipes and patches.
+    Unified,
+    /// Unified diff with removals in red and additions in green.
+    Colored,
+    /// The two versions in columns across a `width`-column terminal, changes coloured.
+    /// Word diffs are shown inline, as with `Colored`.
+    SideBySide { width: usize },
+}
+
+impl Layout {
+    /// Side by side on a terminal of at least 100 columns, coloured on a narrower one, and
+    /// unified text when standard output is not a terminal or `NO_COLOR` is set.
+    pub fn detect() -> Self {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        if no_color || !std::io::stdout().is_terminal() {
+            return Layout::Unified;
+        }
+        match crossterm::terminal::size() {
+            Ok((columns, _)) if columns >= 100 => Layout::SideBySide { width: columns as usize },
+            _ => Layout::Colored,
+        }
+    }
+}
+
+/// The changes that turn `old` into `new`, line by line, with removals before additions.
+pub fn lines<'a>(old: &'a str, new: &'a str) -> Vec<(Change, &'a str)> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    diff(&old, &new)
+}
+
+/// The changes that turn `old` into `new`, word by word. Whitespace runs and punctuation are
+/// tokens of their own, so joining the tokens of either side gives back that side.
+pub fn words<'a>(old: &'a str, new: &'a str) -> Vec<(Change, &'a str)> {
+    diff(&tokens(old), &tokens(new))
+}
+
+fn tokens(text: &str) -> Vec<&str> {
+    // 0: word characters, 1: whitespace, 2: anything else, one character per token
+    let class = |c: char| if c.is_alphanumeric() || c == '_' { 0 } else if c.is_whitespace() { 1 } else { 2 };
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut previous = None;
+    for (i, c) in text.char_indices() {
+        let current = class(c);
+        if previous.is_some_and(|previous| previous != current || current == 2) {
+            tokens.push(&text[start..i]);
+            start = i;
+        }
+        previous = Some(current);
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+/// A longest-common-subsequence diff of two token lists. Common ends are matched first,
+/// so near-identical responses stay cheap.
+fn diff<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(Change, &'a str)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let (middle_old, middle_new) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+    let mut changes: Vec<(Change, &str)> = old[..prefix].iter().map(|token| (Change::Same, *token)).collect();
+
+    let (n, m) = (middle_old.len(), middle_new.len());
+    if (n + 1).saturating_mul(m + 1) > MAX_CELLS {
+        changes.extend(middle_old.iter().map(|token| (Change::Removed, *token)));
+        changes.extend(middle_new.iter().map(|token| (Change::Added, *token)));
+    } else {
+        // lengths[i][j]: longest common subsequence of middle_old[i..] and middle_new[j..]
+        let mut lengths = vec![vec![0u32; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lengths[i][j] = if middle_old[i] == middle_new[j] {
+                    lengths[i + 1][j + 1] + 1
+                } else {
+                    lengths[i + 1][j].max(lengths[i][j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && middle_old[i] == middle_new[j] {
+                changes.push((Change::Same, middle_old[i]));
+                i += 1;
+                j += 1;
+            } else if i < n && (j == m || lengths[i + 1][j] >= lengths[i][j + 1]) {
+                changes.push((Change::Removed, middle_old[i]));
+                i += 1;
+            } else {
+                changes.push((Change::Added, middle_new[j]));
+                j += 1;
+            }
+        }
+    }
+    changes.extend(old[old.len() - suffix..].iter().map(|token| (Change::Same, *token)));
+    changes
+}
+
+/// Renders the diff from `old` to `new`, labelled `old_label` and `new_label`. Only the
+/// changed lines and `CONTEXT` lines around them are shown, in hunks headed like
+/// `@@ -3,4 +3,5 @@`. A word diff marks changes within the lines as `[-old-]{+new+}`, or
+/// in colour.
+pub fn render(old_label: &str, old: &str, new_label: &str, new: &str, by: Granularity, layout: Layout) -> String {
+    let color = layout != Layout::Unified;
+    let changes = lines(old, new);
+    let mut out = vec![paint(&format!("--- {}", old_label), BOLD, color), paint(&format!("+++ {}", new_label), BOLD, color)];
+    if changes.iter().all(|(change, _)| *change == Change::Same) {
+        out.push("(no differences)".to_string());
+        return out.join("\n");
+    }
+
+    // Lines of each side before each change, for the hunk headers
+    let mut positions = Vec::with_capacity(changes.len());
+    let (mut old_line, mut new_line) = (0, 0);
+    for (change, _) in &changes {
+        positions.push((old_line, new_line));
+        match change {
+            Change::Same => {
+                old_line += 1;
+                new_line += 1;
+            },
+            Change::Removed => old_line += 1,
+            Change::Added => new_line += 1,
+        }
+    }
+
+    for (start, end) in hunks(&changes) {
+        let hunk = &changes[start..=end];
+        let old_count = hunk.iter().filter(|(change, _)| *change != Change::Added).count();
+        let new_count = hunk.iter().filter(|(change, _)| *change != Change::Removed).count();
+        // An empty side is numbered by the line before it, as `diff -u` does
+        let (old_start, new_start) = positions[start];
+        let header = format!(
+            "@@ -{},{} +{},{} @@",
+            if old_count > 0 { old_start + 1 } else { old_start }, old_count,
+            if new_count > 0 { new_start + 1 } else { new_start }, new_count
+        );
+        out.push(paint(&header, CYAN, color));
+        match (by, layout) {
+            (Granularity::Words, _) => out.push(word_hunk(hunk, color)),
+            (Granularity::Lines, Layout::SideBySide { width }) => out.extend(side_by_side(hunk, width)),
+            (Granularity::Lines, _) => out.extend(hunk.iter().map(|(change, line)| match change {
+                Change::Same => format!(" {}", line),
+                Change::Removed => paint(&format!("-{}", line), RED, color),
+                Change::Added => paint(&format!("+{}", line), GREEN, color),
+            })),
+        }
+    }
+    out.join("\n")
+}
+
+/// Index ranges of `changes` to show: each change with `CONTEXT` lines around it, merging
+/// changes whose context would touch.
+fn hunks(changes: &[(Change, &str)]) -> Vec<(usize, usize)> {
+    let changed: Vec<usize> = changes.iter().enumerate().filter(|(_, (change, _))| *change != Change::Same).map(|(i, _)| i).collect();
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for i in changed {
+        let (start, end) = (i.saturating_sub(CONTEXT), (i + CONTEXT).min(changes.len() - 1));
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 + 1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    hunks
+}
+
+/// A hunk with the removed and added lines of each change compared word by word.
+fn word_hunk(hunk: &[(Change, &str)], color: bool) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < hunk.len() {
+        if hunk[i].0 == Change::Same {
+            out.push(hunk[i].1.to_string());
+            i += 1;
+            continue;
+        }
+        let removed: Vec<&str> = hunk[i..].iter().take_while(|(change, _)| *change == Change::Removed).map(|(_, line)| *line).collect();
+        i += removed.len();
+        let added: Vec<&str> = hunk[i..].iter().take_while(|(change, _)| *change == Change::Added).map(|(_, line)| *line).collect();
+        i += added.len();
+
+        let (old, new) = (removed.join("\n"), added.join("\n"));
+        let changes = words(&old, &new);
+        let mut text = String::new();
+        let mut j = 0;
+        while j < changes.len() {
+            let change = changes[j].0;
+            let run: String = changes[j..].iter().take_while(|(c, _)| *c == change).map(|(_, token)| *token).collect();
+            j += changes[j..].iter().take_while(|(c, _)| *c == change).count();
+            text.push_str(&match (change, color) {
+                (Change::Same, _) => run,
+                (Change::Removed, true) => format!("{}{}{}", RED, run, RESET),
+                (Change::Added, true) => format!("{}{}{}", GREEN, run, RESET),
+                (Change::Removed, false) => format!("[-{}-]", run),
+                (Change::Added, false) => format!("{{+{}+}}", run),
+            });
+        }
+        out.push(text);
+    }
+    out.join("\n")
+}
+
+/// A line of a diff and whether it changed.
+type Entry<'a> = (Change, &'a str);
+
+/// A hunk in two columns: unchanged lines on both sides, each removed line beside the added
+/// line that replaced it. Long lines wrap within their column.
+fn side_by_side(hunk: &[(Change, &str)], width: usize) -> Vec<String> {
+    let column = width.saturating_sub(3).max(20) / 2;
+    let mut pairs: Vec<(Option<Entry>, Option<Entry>)> = Vec::new();
+    let mut i = 0;
+    while i < hunk.len() {
+        if hunk[i].0 == Change::Same {
+            pairs.push((Some(hunk[i]), Some(hunk[i])));
+            i += 1;
+            continue;
+        }
+        let removed: Vec<Entry> = hunk[i..].iter().take_while(|(change, _)| *change == Change::Removed).copied().collect();
+        i += removed.len();
+        let added: Vec<Entry> = hunk[i..].iter().take_while(|(change, _)| *change == Change::Added).copied().collect();
+        i += added.len();
+        for k in 0..removed.len().max(added.len()) {
+            pairs.push((removed.get(k).copied(), added.get(k).copied()));
+        }
+    }
+
+    let mut out = Vec::new();
+    for (left, right) in pairs {
+        let left_lines = wrap(left.map(|(_, line)| line).unwrap_or_default(), column);
+        let right_lines = wrap(right.map(|(_, line)| line).unwrap_or_default(), column);
+        for k in 0..left_lines.len().max(right_lines.len()) {
+            let cell = |side: Option<Entry>, lines: &[String]| {
+                let text = lines.get(k).cloned().unwrap_or_default();
+                let padded = format!("{}{}", text, " ".repeat(column - text.chars().count()));
+                match side.map(|(change, _)| change) {
+                    Some(Change::Removed) => paint(&padded, RED, true),
+                    Some(Change::Added) => paint(&padded, GREEN, true),
+                    _ => padded,
+                }
+            };
+            out.push(format!("{} │ {}", cell(left, &left_lines), cell(right, &right_lines)).trim_end().to_string());
+        }
+    }
+    out
+}
+
+/// `line` cut into pieces of at most `column` characters, tabs expanded; at least one piece.
+fn wrap(line: &str, column: usize) -> Vec<String> {
+    let chars: Vec<char> = line.replace('\t', "    ").chars().collect();
+    if chars.is_empty() {
+        return vec![String::new()];
+    }
+    chars.chunks(column).map(|chunk| chunk.iter().collect()).collect()
+}
+
+fn paint(text: &str, code: &str, color: bool) -> String {
+    if color {
+        format!("{}{}{}", code, text, RESET)
+    } else {
+        text.to_string()
+    }
+}
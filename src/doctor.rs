@@ -0,0 +1,222 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use crate::{Config, ToolConfig, ToolManager, Scheduler, Validator, CliError};
+use log::info;
+use tokio::task;
+
+/// Prompt sent by the authentication and latency probe.
+pub const PROBE_PROMPT: &str = "Reply with the single word OK";
+pub const VERSION_TIMEOUT_SECS: u64 = 10;
+pub const DEFAULT_VERSION_ARGS: &[&str] = &["--version"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    Fail,
+    Skipped,
+}
+
+impl CheckStatus {
+    fn icon(&self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "✅",
+            CheckStatus::Fail => "❌",
+            CheckStatus::Skipped => "➖",
+        }
+    }
+}
+
+/// Result of every check run against one configured tool.
+#[derive(Debug, Clone)]
+pub struct ToolDiagnosis {
+    pub key: String,
+    pub command: String,
+    pub allowed: bool,
+    pub path: Option<PathBuf>,
+    pub version: Option<String>,
+    pub auth: CheckStatus,
+    pub latency: Option<Duration>,
+    /// Fix-it suggestions for every failed check.
+    pub hints: Vec<String>,
+}
+
+impl ToolDiagnosis {
+    pub fn is_healthy(&self) -> bool {
+        self.hints.is_empty()
+    }
+}
+
+/// Terminal columns taken by `text`, counting the status emoji as double width.
+fn display_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| if matches!(c, '✅' | '❌' | '➖') || c as u32 >= 0x1F300 { 2 } else { 1 })
+        .sum()
+}
+
+pub struct Doctor;
+
+impl Doctor {
+    /// Runs the allowlist, path, version and (optionally) auth/latency checks for one tool.
+    pub async fn diagnose(key: &str, tool: &ToolConfig, probe: bool) -> ToolDiagnosis {
+        let mut diagnosis = ToolDiagnosis {
+            key: key.to_string(),
+            command: tool.command.clone(),
+            allowed: Validator::validate_command(&tool.command).is_ok(),
+            path: ToolManager::resolve_command(&tool.command),
+            version: None,
+            auth: CheckStatus::Skipped,
+            latency: None,
+            hints: Vec::new(),
+        };
+
+        if !diagnosis.allowed {
+            diagnosis.hints.push(format!(
+                "'{}' is not in the command allowlist; point the tool at one of the allowed CLIs",
+                tool.command
+            ));
+        }
+        if diagnosis.path.is_none() {
+            diagnosis.hints.push(format!(
+                "'{}' was not found on PATH; install it or add its directory to PATH",
+                tool.command
+            ));
+        }
+        if !diagnosis.allowed || diagnosis.path.is_none() {
+            return diagnosis;
+        }
+
+        diagnosis.version = Self::version(tool).await;
+        if diagnosis.version.is_none() {
+            diagnosis.hints.push(format!(
+                "'{} {}' printed nothing; set version_args for this tool",
+                tool.command,
+                Self::version_args(tool).join(" ")
+            ));
+        }
+
+        if probe {
+            let start = Instant::now();
+            match ToolManager::run_tool(tool, PROBE_PROMPT).await {
+                Ok(output) if Validator::is_authentication_error(&output) => {
+                    diagnosis.auth = CheckStatus::Fail;
+                    diagnosis.hints.push(format!(
+                        "'{}' reported an authentication error; log in or set its API key",
+                        tool.command
+                    ));
+                },
+                Ok(_) => {
+                    diagnosis.auth = CheckStatus::Pass;
+                    diagnosis.latency = Some(start.elapsed());
+                },
+                Err(CliError::ToolTimeout { timeout, .. }) => {
+                    diagnosis.auth = CheckStatus::Fail;
+                    diagnosis.hints.push(format!(
+                        "The test prompt timed out after {}s; check the network or raise timeout_secs",
+                        timeout
+                    ));
+                },
+                Err(e) => {
+                    diagnosis.auth = CheckStatus::Fail;
+                    diagnosis.hints.push(format!("The test prompt failed: {}", e));
+                }
+            }
+        }
+
+        diagnosis
+    }
+
+    fn version_args(tool: &ToolConfig) -> Vec<String> {
+        tool.version_args.clone()
+            .unwrap_or_else(|| DEFAULT_VERSION_ARGS.iter().map(|a| a.to_string()).collect())
+    }
+
+    /// First non-empty line printed by the tool's version command.
+    pub(crate) async fn version(tool: &ToolConfig) -> Option<String> {
+        let version_tool = ToolConfig {
+            args: Self::version_args(tool),
+            timeout_secs: Some(VERSION_TIMEOUT_SECS),
+            ..tool.clone()
+        };
+
+        let output = ToolManager::run_tool(&version_tool, "version").await.ok()?;
+        Validator::strip_ansi_codes(&output)
+            .lines()
+            .map(|line| line.trim())
+            .find(|line| !line.is_empty())
+            .map(|line| line.to_string())
+    }
+
+    /// Diagnoses the selected tools (all tools when `only` is empty) concurrently, sorted by key.
+    pub async fn run(config: &Config, only: &[String], probe: bool) -> Vec<ToolDiagnosis> {
+        let mut keys: Vec<&String> = config.tools.keys()
+            .filter(|key| only.is_empty() || only.contains(key))
+            .collect();
+        keys.sort();
+
+        // Probes obey the same limits as a run, so local models are not loaded all at once
+        let selected: Vec<(String, ToolConfig)> = keys.into_iter().map(|key| (key.clone(), config.tools[key].clone())).collect();
+        let scheduler = Scheduler::new(config.max_parallel, config.tools.values());
+        let tasks: Vec<_> = selected.into_iter()
+            .map(|(key, tool)| {
+                let scheduler = scheduler.clone();
+                task::spawn(async move {
+                    let _slot = scheduler.acquire(&tool).await;
+                    Self::diagnose(&key, &tool, probe).await
+                })
+            })
+            .collect();
+
+        let mut results = Vec::new();
+        for task in tasks {
+            if let Ok(diagnosis) = task.await {
+                info!("Diagnosed {}: {} issue(s)", diagnosis.key, diagnosis.hints.len());
+                results.push(diagnosis);
+            }
+        }
+        results
+    }
+
+    pub fn render_table(results: &[ToolDiagnosis]) -> String {
+        let rows: Vec<[String; 6]> = results.iter()
+            .map(|d| [
+                d.key.clone(),
+                if d.allowed { CheckStatus::Pass } else { CheckStatus::Fail }.icon().to_string(),
+                d.path.as_ref().map(|p| p.display().to_string()).unwrap_or_else(|| "not found".to_string()),
+                d.version.clone().unwrap_or_else(|| "-".to_string()),
+                d.auth.icon().to_string(),
+                d.latency.map(|l| format!("{:.1}s", l.as_secs_f32())).unwrap_or_else(|| "-".to_string()),
+            ])
+            .collect();
+
+        let headers = ["Tool", "Allowed", "Path", "Version", "Auth", "Latency"];
+        let mut widths: Vec<usize> = headers.iter().map(|h| display_width(h)).collect();
+        for row in &rows {
+            for (i, cell) in row.iter().enumerate() {
+                widths[i] = widths[i].max(display_width(cell));
+            }
+        }
+
+        let format_row = |cells: Vec<&str>| cells.iter().enumerate()
+            .map(|(i, cell)| format!("{}{}", cell, " ".repeat(widths[i] - display_width(cell))))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string();
+
+        let mut table = vec![format_row(headers.to_vec())];
+        for row in &rows {
+            table.push(format_row(row.iter().map(|c| c.as_str()).collect()));
+        }
+
+        let failing: Vec<&ToolDiagnosis> = results.iter().filter(|d| !d.is_healthy()).collect();
+        if !failing.is_empty() {
+            table.push(String::new());
+            for diagnosis in failing {
+                for hint in &diagnosis.hints {
+                    table.push(format!("💡 {}: {}", diagnosis.key, hint));
+                }
+            }
+        }
+        table.join("\n")
+    }
+}
@@ -0,0 +1,199 @@
+use serde::Serialize;
+use crate::{ToolConfig, ToolManager, Validator, Result};
+use crate::{constants::{CPU_LIMIT_SECS, MEMORY_LIMIT_MB, SANDBOX_BASE_ENV, TOOL_TIMEOUT_SECS}, output::OutputFormat};
+
+/// One process a run would start, exactly as it would be started.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Invocation {
+    /// Why the tool runs: `solver`, `fallback for <solver>`, `judge`, `reconcile`, ...
+    pub stage: String,
+    pub tool: String,
+    /// The program and its arguments after `Validator::sanitize_args`.
+    pub argv: Vec<String>,
+    /// Whether the tool starts with this process's environment.
+    pub inherits_env: bool,
+    /// Variables set for the tool. Values interpolated from `${VAR}` are shown as configured,
+    /// and variables kept from this process by name only.
+    pub env: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    pub limits: Limits,
+    /// The prompt as the tool receives it, after sanitization.
+    pub prompt: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Limits {
+    pub timeout_secs: u64,
+    pub memory_mb: u64,
+    pub cpu_secs: u64,
+    /// Longest consensus input before responses are condensed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_input_chars: Option<usize>,
+    /// Sandbox restrictions, such as `landlock` or `isolate_network`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sandbox: Vec<String>,
+}
+
+impl Invocation {
+    /// Describes how `tool` would be started with `prompt`, without starting it.
+    pub fn new(stage: &str, key: &str, tool: &ToolConfig, prompt: &str) -> Result<Self> {
+        let program = ToolManager::resolve_command(&tool.command)
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|| tool.command.clone());
+        let mut argv = vec![program];
+        argv.extend(Validator::sanitize_args(&tool.args, prompt)?);
+
+        // The same order `ToolManager::run_tool` applies them in
+        let mut env: Vec<String> = Vec::new();
+        if let Some(sandbox) = &tool.sandbox {
+            let mut names: Vec<&str> = SANDBOX_BASE_ENV.to_vec();
+            names.extend(sandbox.env_allowlist.iter().map(|name| name.as_str()));
+            names.sort();
+            names.dedup();
+            env.extend(names.into_iter().filter(|name| std::env::var_os(name).is_some()).map(|name| name.to_string()));
+            if sandbox.private_tmp {
+                env.extend(["TMPDIR", "TMP", "TEMP"].iter().map(|name| format!("{}=<private directory>", name)));
+            }
+        }
+        if tool.sandbox.is_some() || tool.env_clear {
+            env.extend(tool.env_passthrough.iter().filter(|name| std::env::var_os(name).is_some()).cloned());
+        }
+        // Fails like the run would on an unset variable without a default
+        tool.environment(&|name| std::env::var(name).ok())?;
+        let mut names: Vec<&String> = tool.env.keys().collect();
+        names.sort();
+        env.extend(names.into_iter().map(|name| format!("{}={}", name, tool.env[name])));
+
+        let cwd = match &tool.sandbox {
+            Some(sandbox) if sandbox.private_tmp => Some("<private directory>".to_string()),
+            _ => tool.working_dir().map(|dir| dir.display().to_string()),
+        };
+        let sandbox = tool.sandbox.as_ref().map(|sandbox| {
+            [("private_tmp", sandbox.private_tmp), ("landlock", sandbox.landlock), ("seccomp", sandbox.seccomp), ("isolate_network", sandbox.isolate_network)]
+                .iter()
+                .filter(|(_, on)| *on)
+                .map(|(name, _)| name.to_string())
+                .collect()
+        }).unwrap_or_default();
+
+        Ok(Invocation {
+            stage: stage.to_string(),
+            tool: key.to_string(),
+            argv,
+            inherits_env: tool.sandbox.is_none() && !tool.env_clear,
+            env,
+            cwd,
+            limits: Limits {
+                timeout_secs: tool.timeout_secs.unwrap_or(TOOL_TIMEOUT_SECS),
+                memory_mb: MEMORY_LIMIT_MB,
+                cpu_secs: CPU_LIMIT_SECS,
+                max_input_chars: tool.max_input_chars,
+                sandbox,
+            },
+            prompt: Validator::sanitize_prompt(prompt)?,
+        })
+    }
+
+    /// The argv with the argument that carries the prompt shown as `<prompt>`.
+    fn command_line(&self) -> String {
+        let argv: Vec<String> = self.argv.iter()
+            .map(|arg| if *arg == self.prompt { "<prompt>".to_string() } else { shell_words(std::slice::from_ref(arg)) })
+            .collect();
+        argv.join(" ")
+    }
+}
+
+/// What a run would do, from `--dry-run`.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct DryRun {
+    pub strategy: String,
+    pub invocations: Vec<Invocation>,
+    /// What happens between and instead of the invocations, e.g. the seed of the judge's order.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+impl DryRun {
+    pub fn render(&self, format: OutputFormat) -> String {
+        match format {
+            OutputFormat::Json => serde_json::to_string_pretty(self).unwrap_or_else(|e| format!("{{\"error\": \"{}\"}}", e)),
+            OutputFormat::Markdown => {
+                let mut md = vec![format!("# Dry run ({} strategy)", self.strategy)];
+                for invocation in &self.invocations {
+                    md.push(format!("\n## {}: {}\n", invocation.stage, invocation.tool));
+                    md.push(format!("- **argv:** `{}`", invocation.command_line().replace('`', "'")));
+                    md.push(format!("- **env:** {}", describe_env(invocation)));
+                    if let Some(cwd) = &invocation.cwd {
+                        md.push(format!("- **cwd:** {}", cwd));
+                    }
+                    md.push(format!("- **limits:** {}", describe_limits(&invocation.limits)));
+                    md.push(format!("\n```text\n{}\n```", invocation.prompt));
+                }
+                if !self.notes.is_empty() {
+                    md.push(String::new());
+                    md.extend(self.notes.iter().map(|note| format!("_{}_", note)));
+                }
+                md.join("\n")
+            },
+            OutputFormat::Text => {
+                let mut text = vec![format!("🧪 Dry run ({} strategy); no tool was started", self.strategy)];
+                for invocation in &self.invocations {
+                    text.push(format!("\n▶ {}: {}", invocation.stage, invocation.tool));
+                    text.push(format!("  argv:   {}", invocation.command_line()));
+                    text.push(format!("  env:    {}", describe_env(invocation)));
+                    if let Some(cwd) = &invocation.cwd {
+                        text.push(format!("  cwd:    {}", cwd));
+                    }
+                    text.push(format!("  limits: {}", describe_limits(&invocation.limits)));
+                    text.push("  prompt:".to_string());
+                    text.extend(invocation.prompt.lines().map(|line| format!("    │ {}", line)));
+                }
+                if !self.notes.is_empty() {
+                    text.push(String::new());
+                    text.extend(self.notes.iter().map(|note| format!("ℹ️  {}", note)));
+                }
+                text.join("\n")
+            },
+        }
+    }
+}
+
+fn describe_env(invocation: &Invocation) -> String {
+    let base = if invocation.inherits_env { "inherited" } else { "cleared" };
+    if invocation.env.is_empty() {
+        base.to_string()
+    } else {
+        format!("{}, plus {}", base, invocation.env.join(" "))
+    }
+}
+
+fn describe_limits(limits: &Limits) -> String {
+    let mut parts = vec![
+        format!("timeout {}s", limits.timeout_secs),
+        format!("memory {} MB", limits.memory_mb),
+        format!("CPU {}s", limits.cpu_secs),
+    ];
+    if let Some(chars) = limits.max_input_chars {
+        parts.push(format!("max input {} chars", chars));
+    }
+    if !limits.sandbox.is_empty() {
+        parts.push(format!("sandbox: {}", limits.sandbox.join(", ")));
+    }
+    parts.join(", ")
+}
+
+/// `argv` as it could be typed into a POSIX shell.
+pub fn shell_words(argv: &[String]) -> String {
+    argv.iter()
+        .map(|arg| {
+            let plain = !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:,@%+".contains(c));
+            if plain {
+                arg.clone()
+            } else {
+                format!("'{}'", arg.replace('\'', "'\\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -8,6 +8,9 @@ pub enum CliError {
     #[error("Tool '{tool}' timed out after {timeout}s")]
     ToolTimeout { tool: String, timeout: u64 },
     
+    #[error("Tool '{tool}' was cancelled")]
+    ToolCancelled { tool: String },
+    
     #[error("Authentication failed for tool '{tool}': {reason}")]
     AuthenticationFailed { tool: String, reason: String },
     
@@ -23,6 +26,21 @@ pub enum CliError {
     #[error("All solver tools failed")]
     AllSolversFailed,
     
+    #[error("All {count} responses were quarantined as possible prompt injections")]
+    AllQuarantined { count: usize },
+    
+    #[error("Prompt contains sensitive data ({kinds}) and {reason}")]
+    SensitiveData { kinds: String, reason: String },
+    
+    #[error("Local-only run cannot proceed: {reason}")]
+    LocalOnly { reason: String },
+    
+    #[error("Cannot sandbox tool '{tool}': {reason}")]
+    SandboxError { tool: String, reason: String },
+    
+    #[error("No consensus tool succeeded: {reason}")]
+    ConsensusFailed { reason: String },
+    
     #[error("Configuration error: {message}")]
     ConfigError { message: String },
 }
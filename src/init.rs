@@ -0,0 +1,248 @@
+use std::path::PathBuf;
+use crate::{Doctor, ToolConfig, ToolManager, Validator};
+use log::info;
+
+/// Model used for the `ollama` entry when `ollama list` shows nothing.
+pub const DEFAULT_OLLAMA_MODEL: &str = "llama3.2:1b";
+/// Tools preferred as the consensus judge of the generated default profile, best first.
+pub const PREFERRED_JUDGES: &[&str] = &["claude", "gemini", "openai", "q", "codex"];
+pub const DEFAULT_PROFILE_SOLVERS: usize = 3;
+
+/// An AI CLI that `ai-co init` knows how to configure.
+#[derive(Debug)]
+pub struct KnownTool {
+    pub key: &'static str,
+    pub name: &'static str,
+    pub command: &'static str,
+    pub description: &'static str,
+    pub tags: &'static [&'static str],
+    /// Argument templates known to work, the recommended one first.
+    pub templates: &'static [&'static [&'static str]],
+    /// Runs models on this machine (written as `privacy = "local"`).
+    pub local: bool,
+}
+
+pub const KNOWN_TOOLS: &[KnownTool] = &[
+    KnownTool {
+        key: "q",
+        name: "Amazon Q",
+        command: "q",
+        description: "AWS AI assistant, great for cloud/infrastructure questions",
+        tags: &["cloud", "aws", "infrastructure"],
+        templates: &[&["chat", "--no-interactive", "{prompt}"], &["chat", "{prompt}"]],
+        local: false,
+    },
+    KnownTool {
+        key: "gemini",
+        name: "Google Gemini",
+        command: "gemini",
+        description: "Strong general-purpose AI, good for coding and analysis",
+        tags: &["code", "reasoning", "general"],
+        templates: &[&["--prompt", "{prompt}"]],
+        local: false,
+    },
+    KnownTool {
+        key: "claude",
+        name: "Anthropic Claude",
+        command: "claude",
+        description: "Excellent for writing, reasoning, and complex tasks",
+        tags: &["writing", "reasoning", "code", "general"],
+        templates: &[&["-p", "{prompt}"]],
+        local: false,
+    },
+    KnownTool {
+        key: "openai",
+        name: "OpenAI ChatGPT",
+        command: "openai",
+        description: "Versatile AI, strong for creative and general tasks",
+        tags: &["writing", "general"],
+        templates: &[
+            &["api", "chat.completions.create", "-m", "gpt-4o-mini", "-g", "user", "{prompt}"],
+            &["api", "chat.completions.create", "-m", "gpt-3.5-turbo", "-g", "user", "{prompt}"],
+        ],
+        local: false,
+    },
+    KnownTool {
+        key: "codex",
+        name: "OpenAI Codex",
+        command: "codex",
+        description: "Purpose-built for code generation and developer workflows",
+        tags: &["code"],
+        templates: &[&["exec", "{prompt}"]],
+        local: false,
+    },
+    KnownTool {
+        key: "ollama",
+        name: "Local LLM",
+        command: "ollama",
+        description: "Privacy-focused, runs offline, good for sensitive data",
+        tags: &["privacy", "local"],
+        templates: &[&["run", DEFAULT_OLLAMA_MODEL, "{prompt}"]],
+        local: true,
+    },
+    KnownTool {
+        key: "mistral",
+        name: "Mistral AI",
+        command: "mistral",
+        description: "European AI model, strong multilingual support",
+        tags: &["multilingual", "writing"],
+        templates: &[&["chat", "{prompt}"]],
+        local: false,
+    },
+];
+
+/// A known tool found on `PATH`.
+#[derive(Debug, Clone)]
+pub struct DiscoveredTool {
+    pub known: &'static KnownTool,
+    pub path: PathBuf,
+    pub version: Option<String>,
+}
+
+/// A tool chosen for the generated configuration.
+#[derive(Debug, Clone)]
+pub struct Selection {
+    pub key: String,
+    pub known: &'static KnownTool,
+    pub name: String,
+    pub description: String,
+    pub args: Vec<String>,
+    /// Shown as a comment above the tool, e.g. the version and path found.
+    pub note: Option<String>,
+}
+
+impl Selection {
+    pub fn new(discovered: &DiscoveredTool, template: usize) -> Self {
+        let known = discovered.known;
+        let template = known.templates.get(template).unwrap_or(&known.templates[0]);
+        Selection {
+            key: known.key.to_string(),
+            known,
+            name: known.name.to_string(),
+            description: known.description.to_string(),
+            args: template.iter().map(|a| a.to_string()).collect(),
+            note: Some(format!(
+                "Found {} at {}",
+                discovered.version.as_deref().unwrap_or("unknown version"),
+                discovered.path.display()
+            )),
+        }
+    }
+
+    /// One entry per local Ollama model; the first keeps the plain `ollama` key.
+    pub fn ollama(discovered: &DiscoveredTool, model: &str, first: bool) -> Self {
+        let mut selection = Self::new(discovered, 0);
+        selection.args = vec!["run".to_string(), model.to_string(), "{prompt}".to_string()];
+        if !first {
+            let suffix: String = model.chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+                .collect();
+            selection.key = format!("ollama-{}", suffix.trim_matches('-'));
+            selection.name = format!("Local LLM ({})", model);
+        }
+        selection
+    }
+}
+
+pub struct Initializer;
+
+impl Initializer {
+    /// Finds every known tool on `PATH` and probes its version.
+    pub async fn discover() -> Vec<DiscoveredTool> {
+        let mut found = Vec::new();
+        for known in KNOWN_TOOLS {
+            let Some(path) = ToolManager::resolve_command(known.command) else {
+                continue;
+            };
+            let tool = Self::tool_config(known, &[]);
+            let version = Doctor::version(&tool).await;
+            info!("Discovered {} at {}", known.command, path.display());
+            found.push(DiscoveredTool { known, path, version });
+        }
+        found
+    }
+
+    fn tool_config(known: &KnownTool, args: &[&str]) -> ToolConfig {
+        ToolConfig {
+            name: known.name.to_string(),
+            command: known.command.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            description: known.description.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Models installed in the local Ollama, as listed by `ollama list`.
+    pub async fn ollama_models() -> Vec<String> {
+        let Some(known) = KNOWN_TOOLS.iter().find(|k| k.key == "ollama") else {
+            return Vec::new();
+        };
+        let tool = ToolConfig {
+            timeout_secs: Some(crate::doctor::VERSION_TIMEOUT_SECS),
+            ..Self::tool_config(known, &["list"])
+        };
+        match ToolManager::run_tool(&tool, "list").await {
+            Ok(output) => Self::parse_ollama_list(&output),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Extracts the model names (first column) from `ollama list` output.
+    pub fn parse_ollama_list(output: &str) -> Vec<String> {
+        Validator::strip_ansi_codes(output)
+            .lines()
+            .filter(|line| !line.trim_start().starts_with("NAME"))
+            .filter_map(|line| line.split_whitespace().next())
+            .filter(|name| name.chars().all(|c| c.is_ascii_alphanumeric() || "-_.:/".contains(c)))
+            .map(|name| name.to_string())
+            .collect()
+    }
+
+    /// Renders a commented `config.toml` for the selected tools, with a default profile.
+    pub fn render(selections: &[Selection]) -> String {
+        let quote = |s: &str| toml::Value::String(s.to_string()).to_string();
+        let list = |items: &[String]| format!("[{}]", items.iter().map(|i| quote(i)).collect::<Vec<_>>().join(", "));
+
+        let mut out = String::from(
+            "# ai-consensus-cli configuration, generated by `ai-co init`.\n\
+             # Check it with `ai-co config check`; see CONFIG.md for every option.\n\n"
+        );
+        if !selections.is_empty() {
+            out.push_str("# Profile used when neither -s/-c nor --profile is given\ndefault_profile = \"default\"\n");
+        }
+
+        for selection in selections {
+            out.push('\n');
+            if let Some(note) = &selection.note {
+                out.push_str(&format!("# {}\n", note));
+            }
+            out.push_str(&format!("[tools.{}]\n", selection.key));
+            out.push_str(&format!("name = {}\n", quote(&selection.name)));
+            out.push_str(&format!("command = {}\n", quote(selection.known.command)));
+            out.push_str(&format!("args = {}\n", list(&selection.args)));
+            out.push_str(&format!("description = {}\n", quote(&selection.description)));
+            let tags: Vec<String> = selection.known.tags.iter().map(|t| t.to_string()).collect();
+            out.push_str(&format!("tags = {}\n", list(&tags)));
+            if selection.known.local {
+                out.push_str("privacy = \"local\"\n");
+            }
+        }
+
+        if let Some(first) = selections.first() {
+            let solvers: Vec<String> = selections.iter()
+                .take(DEFAULT_PROFILE_SOLVERS)
+                .map(|s| s.key.clone())
+                .collect();
+            let judge = PREFERRED_JUDGES.iter()
+                .find_map(|key| selections.iter().find(|s| s.key == *key))
+                .unwrap_or(first);
+
+            out.push_str("\n# Run with: ai-co -p \"Your question\" (or --profile default)\n");
+            out.push_str("[profiles.default]\n");
+            out.push_str("description = \"Tools found by ai-co init\"\n");
+            out.push_str(&format!("solvers = {}\n", list(&solvers)));
+            out.push_str(&format!("consensus = {}\n", quote(&judge.key)));
+        }
+        out
+    }
+}
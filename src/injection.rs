@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+use crate::{CliError, Result, Validator};
+
+/// The `[injection]` section.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct InjectionConfig {
+    /// Leave flagged responses out of the consensus instead of only reporting them.
+    #[serde(default)]
+    pub quarantine: bool,
+    /// Extra case-insensitive regexes, checked along with `INJECTION_PATTERNS`.
+    #[serde(default)]
+    pub patterns: Vec<String>,
+}
+
+impl InjectionConfig {
+    pub fn validate(&self) -> Result<()> {
+        for pattern in &self.patterns {
+            regex::Regex::new(pattern).map_err(|e| CliError::ConfigError {
+                message: format!("Injection pattern '{}' is not a valid regex: {}", pattern, e)
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// A solver response that looks like it tries to instruct the consensus tool.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Flagged {
+    /// 1-based position in the report's `responses`.
+    pub response: usize,
+    /// The suspicious text that matched.
+    pub matches: Vec<String>,
+    /// Whether the response was left out of the consensus.
+    pub quarantined: bool,
+}
+
+/// Responses split into those passed on to the consensus and the flagged ones.
+#[derive(Debug, Clone, Default)]
+pub struct Screened {
+    pub kept: Vec<String>,
+    /// 1-based position of each kept response among all responses.
+    pub positions: Vec<usize>,
+    pub flagged: Vec<Flagged>,
+}
+
+/// Scans every response for injection patterns, dropping flagged ones when `quarantine` is set.
+pub fn screen(responses: &[String], config: &InjectionConfig, quarantine: bool) -> Result<Screened> {
+    let mut screened = Screened::default();
+    for (i, response) in responses.iter().enumerate() {
+        let matches = Validator::detect_injection(response, &config.patterns)?;
+        if matches.is_empty() || !quarantine {
+            screened.kept.push(response.clone());
+            screened.positions.push(i + 1);
+        }
+        if matches.is_empty() {
+            continue;
+        }
+        screened.flagged.push(Flagged { response: i + 1, matches, quarantined: quarantine });
+    }
+
+    if screened.kept.is_empty() && !responses.is_empty() {
+        return Err(CliError::AllQuarantined { count: responses.len() });
+    }
+    Ok(screened)
+}
@@ -0,0 +1,374 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use crate::{Config, CliError, Result, Validator, profile::{ProfileConfig, split_list}};
+use log::{debug, info};
+
+/// File name looked up in the current directory and its ancestors.
+pub const PROJECT_CONFIG_FILE: &str = ".ai-consensus.toml";
+pub const SYSTEM_CONFIG_PATH: &str = "/etc/ai-consensus-cli/config.toml";
+pub const ENV_PREFIX: &str = "AI_CONSENSUS_";
+
+/// Where a configuration value came from, lowest precedence first. A file named with
+/// `--config` or `AI_CONSENSUS_CONFIG` is the one asked for, so it beats the project file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LayerKind {
+    System,
+    User,
+    Project,
+    File,
+    Env,
+    Cli,
+}
+
+impl std::fmt::Display for LayerKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            LayerKind::System => "system",
+            LayerKind::User => "user",
+            LayerKind::Project => "project",
+            LayerKind::File => "file",
+            LayerKind::Env => "env",
+            LayerKind::Cli => "cli",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Layer {
+    pub kind: LayerKind,
+    /// File path or variable name the layer was read from.
+    pub source: String,
+    pub value: toml::Value,
+}
+
+impl Layer {
+    pub fn label(&self) -> String {
+        format!("{} ({})", self.kind, self.source)
+    }
+}
+
+/// A configuration merged from every layer, remembering which layer set each value.
+#[derive(Debug)]
+pub struct LayeredConfig {
+    pub config: Config,
+    pub layers: Vec<Layer>,
+    pub merged: toml::Value,
+    /// Dotted key path (e.g. `tools.q.args`) to the label of the layer that set it.
+    pub origins: BTreeMap<String, String>,
+}
+
+/// Deep-merges `overlay` into `base`: tables merge key by key, everything else is replaced.
+pub fn merge_values(base: &mut toml::Value, overlay: &toml::Value) {
+    match (base, overlay) {
+        (toml::Value::Table(base), toml::Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(key) {
+                    Some(existing) => merge_values(existing, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        },
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
+
+/// Flattens a TOML value into `(dotted.key, leaf)` pairs. Arrays are treated as leaves.
+pub fn flatten(value: &toml::Value) -> Vec<(String, toml::Value)> {
+    fn walk(prefix: &str, value: &toml::Value, out: &mut Vec<(String, toml::Value)>) {
+        match value {
+            toml::Value::Table(table) => {
+                for (key, child) in table {
+                    let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                    walk(&path, child, out);
+                }
+            },
+            leaf => out.push((prefix.to_string(), leaf.clone())),
+        }
+    }
+
+    let mut out = Vec::new();
+    walk("", value, &mut out);
+    out
+}
+
+/// The per-user configuration file, `~/.config/ai-consensus-cli/config.toml`.
+pub fn user_config_path() -> String {
+    let home_dir = std::env::var("HOME").unwrap_or_default();
+    format!("{}/.config/ai-consensus-cli/config.toml", home_dir)
+}
+
+/// Finds the nearest `.ai-consensus.toml` in `start` or one of its ancestors.
+pub fn find_project_config(start: &Path) -> Option<PathBuf> {
+    start.ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG_FILE))
+        .find(|candidate| candidate.is_file())
+}
+
+fn read_layer(kind: LayerKind, path: &str) -> Result<Option<Layer>> {
+    let validated_path = match Validator::validate_config_path(path) {
+        Ok(validated_path) => validated_path,
+        Err(_) => return Ok(None),
+    };
+    let content = match std::fs::read_to_string(&validated_path) {
+        Ok(content) => content,
+        Err(_) => return Ok(None),
+    };
+
+    let value: toml::Value = toml::from_str(&content).map_err(|e| CliError::ConfigError {
+        message: format!("Invalid config format in {}: {}", path, e)
+    })?;
+    debug!("Read {} config layer from {}", kind, path);
+    Ok(Some(Layer { kind, source: path.to_string(), value }))
+}
+
+/// Existing configuration files in precedence order: system, user, project and the explicit
+/// file (`AI_CONSENSUS_CONFIG` or `--config`). Files reachable by two routes appear once.
+pub fn layer_files(config_path: &str) -> Vec<(LayerKind, String)> {
+    layer_files_in(config_path, std::env::current_dir().ok().as_deref())
+}
+
+/// [`layer_files`] with the project file looked up from `cwd`.
+pub fn layer_files_in(config_path: &str, cwd: Option<&Path>) -> Vec<(LayerKind, String)> {
+    let mut candidates: Vec<(LayerKind, String)> = vec![(LayerKind::System, SYSTEM_CONFIG_PATH.to_string())];
+
+    // A config.toml shipped next to the binary acts as installation defaults
+    if let Ok(exe) = std::env::current_exe() {
+        if let Some(parent) = exe.parent() {
+            if let Some(bin_path) = parent.join("config.toml").to_str() {
+                candidates.push((LayerKind::System, bin_path.to_string()));
+            }
+        }
+    }
+
+    candidates.push((LayerKind::User, user_config_path()));
+
+    if let Some(project) = cwd.and_then(find_project_config) {
+        candidates.push((LayerKind::Project, project.to_string_lossy().to_string()));
+    }
+
+    if let Ok(override_path) = std::env::var("AI_CONSENSUS_CONFIG") {
+        candidates.push((LayerKind::File, override_path));
+    }
+    candidates.push((LayerKind::File, config_path.to_string()));
+
+    let mut files = Vec::new();
+    let mut seen: Vec<PathBuf> = Vec::new();
+    for (kind, path) in candidates {
+        let Ok(validated_path) = Validator::validate_config_path(&path) else {
+            continue;
+        };
+        if !validated_path.is_file() {
+            continue;
+        }
+        let canonical = std::fs::canonicalize(&validated_path).unwrap_or(validated_path);
+        if !seen.contains(&canonical) {
+            seen.push(canonical);
+            files.push((kind, path));
+        }
+    }
+    files
+}
+
+/// Config keys that can be set through `AI_CONSENSUS_*` variables.
+fn env_layer() -> Option<Layer> {
+    let mut table = toml::Table::new();
+    let mut sources = Vec::new();
+
+    if let Ok(profile) = std::env::var(format!("{}PROFILE", ENV_PREFIX)) {
+        table.insert("default_profile".to_string(), toml::Value::String(profile));
+        sources.push(format!("{}PROFILE", ENV_PREFIX));
+    }
+
+    if table.is_empty() {
+        None
+    } else {
+        Some(Layer { kind: LayerKind::Env, source: sources.join(", "), value: toml::Value::Table(table) })
+    }
+}
+
+/// Run settings given through `AI_CONSENSUS_*` variables; they sit between the profile and CLI flags.
+pub fn env_overrides() -> Result<ProfileConfig> {
+    let var = |name: &str| std::env::var(format!("{}{}", ENV_PREFIX, name)).ok().filter(|v| !v.trim().is_empty());
+
+    Ok(ProfileConfig {
+        solvers: var("SOLVERS").map(|v| split_list(&v)).unwrap_or_default(),
+        consensus: var("CONSENSUS").map(|v| split_list(&v)).unwrap_or_default(),
+        judges: var("JUDGES")
+            .map(|v| v.parse().ok().filter(|n| *n > 0).ok_or_else(|| CliError::ConfigError {
+                message: format!("{}JUDGES must be a positive number, got '{}'", ENV_PREFIX, v)
+            }))
+            .transpose()?,
+        reconcile: var("RECONCILE").map(|v| v.parse()).transpose()?,
+        weights_from: var("WEIGHTS_FROM"),
+        strategy: var("STRATEGY").map(|v| v.parse()).transpose()?,
+        timeout_secs: var("TIMEOUT")
+            .map(|v| v.parse().map_err(|_| CliError::ConfigError {
+                message: format!("{}TIMEOUT must be a number of seconds, got '{}'", ENV_PREFIX, v)
+            }))
+            .transpose()?,
+        format: var("FORMAT").map(|v| v.parse()).transpose()?,
+        ..Default::default()
+    })
+}
+
+/// Run settings after layering the profile, env and CLI values, with the layer behind each one.
+#[derive(Debug, Clone, Default)]
+pub struct RunSettings {
+    pub profile: Option<String>,
+    pub settings: ProfileConfig,
+    /// Setting name (e.g. `consensus`) to the label of the layer that set it.
+    pub origins: BTreeMap<String, String>,
+}
+
+impl RunSettings {
+    /// Renders the settings as `key = value` lines, optionally annotated with their origin.
+    pub fn describe(&self, with_origin: bool) -> String {
+        let s = &self.settings;
+        let values = [
+            ("profile", self.profile.clone()),
+            ("solvers", (!s.solvers.is_empty()).then(|| s.solvers.join(","))),
+            ("consensus", (!s.consensus.is_empty()).then(|| s.consensus.join(","))),
+            ("judges", s.judges.map(|v| v.to_string())),
+            ("reconcile", s.reconcile.map(|v| v.to_string())),
+            ("weights", (!s.weights.is_empty()).then(|| {
+                let mut weights: Vec<String> = s.weights.iter().map(|(tool, weight)| format!("{}={}", tool, weight)).collect();
+                weights.sort();
+                weights.join(",")
+            })),
+            ("weights_from", s.weights_from.clone()),
+            ("strategy", s.strategy.map(|v| v.to_string())),
+            ("timeout_secs", s.timeout_secs.map(|v| v.to_string())),
+            ("format", s.format.map(|v| format!("{:?}", v).to_lowercase())),
+        ];
+
+        values.iter()
+            .filter_map(|(name, value)| value.as_ref().map(|v| (name, v)))
+            .map(|(name, value)| {
+                let line = format!("{} = {}", name, value);
+                match self.origins.get(*name) {
+                    Some(origin) if with_origin => format!("{:<60} # {}", line, origin),
+                    _ => line,
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl LayeredConfig {
+    /// Reads every available layer (see [`layer_files`]) followed by the env layer.
+    pub fn load(config_path: &str) -> Result<Self> {
+        let mut layers = Vec::new();
+        for (kind, path) in layer_files(config_path) {
+            layers.extend(read_layer(kind, &path)?);
+        }
+
+        if layers.is_empty() {
+            return Err(CliError::ConfigError {
+                message: format!("Could not read config file: {} (tried: {}, ~/.config/ai-consensus-cli/config.toml, {} in current or parent dirs, current dir, binary location)", config_path, SYSTEM_CONFIG_PATH, PROJECT_CONFIG_FILE)
+            });
+        }
+
+        layers.extend(env_layer());
+        Self::from_layers(layers)
+    }
+
+    /// Merges already-read layers, lowest precedence first.
+    pub fn from_layers(layers: Vec<Layer>) -> Result<Self> {
+        let mut merged = toml::Value::Table(toml::Table::new());
+        let mut origins = BTreeMap::new();
+
+        for layer in &layers {
+            merge_values(&mut merged, &layer.value);
+            for (key, _) in flatten(&layer.value) {
+                // A table replaced by a leaf (or vice versa) invalidates origins below it
+                origins.retain(|existing: &String, _| !existing.starts_with(&format!("{}.", key)));
+                origins.insert(key, layer.label());
+            }
+        }
+
+        let config: Config = merged.clone().try_into().map_err(|e| CliError::ConfigError {
+            message: format!("Invalid config format: {}", e)
+        })?;
+        config.validate()?;
+
+        info!("Loaded configuration from {} layer(s) with {} tools", layers.len(), config.tools.len());
+        Ok(Self { config, layers, merged, origins })
+    }
+
+    /// Resolves the run settings: profile (named, or `default_profile`), then `AI_CONSENSUS_*`
+    /// variables, then command-line flags.
+    pub fn run_settings(&self, profile: Option<&str>, cli: &ProfileConfig) -> Result<RunSettings> {
+        let env = env_overrides()?;
+        let profile_name = profile.map(|p| p.to_string()).or_else(|| self.config.default_profile.clone());
+        let base = self.config.resolve_profile(profile_name.as_deref())?;
+
+        let mut origins = BTreeMap::new();
+        if profile_name.is_some() {
+            let origin = match profile {
+                Some(_) => format!("{} (--profile)", LayerKind::Cli),
+                None => self.origins.get("default_profile").cloned().unwrap_or_default(),
+            };
+            origins.insert("profile".to_string(), origin);
+        }
+
+        // (setting, env variable suffix, flag, set in layer)
+        let set_fields = |layer: &ProfileConfig| [
+            ("solvers", "SOLVERS", "--solvers", !layer.solvers.is_empty()),
+            ("consensus", "CONSENSUS", "--consensus", !layer.consensus.is_empty()),
+            ("judges", "JUDGES", "--judges", layer.judges.is_some()),
+            ("reconcile", "RECONCILE", "--reconcile", layer.reconcile.is_some()),
+            ("weights_from", "WEIGHTS_FROM", "--weights-from", layer.weights_from.is_some()),
+            ("strategy", "STRATEGY", "--strategy", layer.strategy.is_some()),
+            ("timeout_secs", "TIMEOUT", "--timeout", layer.timeout_secs.is_some()),
+            ("format", "FORMAT", "--format", layer.format.is_some()),
+        ];
+
+        if let Some(name) = &profile_name {
+            for (field, _, _, set) in set_fields(&base) {
+                if set {
+                    let origin = match self.origins.get(&format!("profiles.{}.{}", name, field)) {
+                        Some(file) => format!("profile {}, {}", name, file),
+                        None => format!("profile {}", name),
+                    };
+                    origins.insert(field.to_string(), origin);
+                }
+            }
+        }
+        for (field, var, _, set) in set_fields(&env) {
+            if set {
+                origins.insert(field.to_string(), format!("{} ({}{})", LayerKind::Env, ENV_PREFIX, var));
+            }
+        }
+        for (field, _, flag, set) in set_fields(cli) {
+            if set {
+                origins.insert(field.to_string(), format!("{} ({})", LayerKind::Cli, flag));
+            }
+        }
+
+        let settings = base.overridden_by(&env).overridden_by(cli);
+        Ok(RunSettings { profile: profile_name, settings, origins })
+    }
+
+    /// Renders the merged configuration as `key = value` lines, optionally annotated with their origin.
+    pub fn describe(&self, with_origin: bool) -> String {
+        flatten(&self.merged).into_iter()
+            .map(|(key, value)| {
+                let line = match &value {
+                    toml::Value::Array(items) if items.iter().any(|i| i.is_table()) => {
+                        format!("{} = [{} entries]", key, items.len())
+                    },
+                    _ => format!("{} = {}", key, value),
+                };
+                match self.origins.get(&key) {
+                    Some(origin) if with_origin => format!("{:<60} # {}", line, origin),
+                    _ => line,
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
@@ -1,27 +1,316 @@
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
 use log::{info, warn};
 
-use ai_consensus_cli::{Config, ToolManager, ConsensusEngine, Validator, ui, Result};
+use ai_consensus_cli::{Config, LayeredConfig, Doctor, ConfigChecker, Severity, Initializer, Selection, RedactionPolicy, redaction, privacy, weights, layers, schema, pipeline, RunOptions, ToolManager, Scheduler, Validator, WorkflowRunner, ProfileConfig, OutputFormat, Strategy, Evaluator, EvalPlan, eval::Scorer, Batch, ui, progress, dashboard::{self, Exit}, RunReport, CliError, diff::{Granularity, Layout}, Result};
 
 #[derive(Parser)]
 #[command(name = "ai-co")]
 #[command(about = "Orchestrate multiple AI CLIs with consensus")]
+#[command(subcommand_negates_reqs = true)]
 struct Cli {
-    #[arg(short, long, value_delimiter = ',')]
-    #[arg(help = "AI tools to solve the problem (comma-separated)")]
-    solvers: Vec<String>,
+    #[command(subcommand)]
+    command: Option<Command>,
+    
+    #[command(flatten)]
+    run: RunArgs,
+    
+    #[arg(long, conflicts_with = "solvers")]
+    #[arg(help = "Pick solvers automatically from the prompt topic and tool tags")]
+    auto: bool,
+    
+    #[arg(long, value_name = "N")]
+    #[arg(help = "Seed for the order responses are shown to the judge, to reproduce a run")]
+    seed: Option<u64>,
+    
+    #[arg(long)]
+    #[arg(help = "Remove phrases like \"As Claude, ...\" from responses before judging")]
+    strip_identity: bool,
+    
+    #[arg(long)]
+    #[arg(help = "Tag each statement of the consensus with the solvers that support it (judge strategy)")]
+    attribute: bool,
+    
+    #[arg(long)]
+    #[arg(help = "Print the commands, environment, limits and prompts of the run without starting any tool")]
+    dry_run: bool,
     
-    #[arg(short, long)]
-    #[arg(help = "AI tool to provide consensus on solver responses")]
-    consensus: String,
+    #[arg(long, conflicts_with = "dry_run")]
+    #[arg(help = "Follow the run in a live dashboard: cancel slow tools, read and diff responses, copy the consensus")]
+    tui: bool,
     
-    #[arg(short, long)]
+    #[arg(long, value_name = "A,B", value_parser = parse_pair)]
+    #[arg(help = "After the answer, print the diff between two responses (tool names or numbers) or a response and `consensus`")]
+    diff: Option<(String, String)>,
+    
+    #[arg(long, value_name = "BY", default_value = "lines")]
+    #[arg(help = "Compare lines or words with --diff")]
+    diff_by: Granularity,
+    
+    #[arg(short, long, required = true, default_value = "", hide_default_value = true)]
     #[arg(help = "Question or problem to solve")]
     prompt: String,
     
-    #[arg(long, default_value = "config.toml")]
-    #[arg(help = "Path to configuration file")]
+    #[arg(long, default_value = "config.toml", global = true)]
+    #[arg(help = "Path to configuration file; its settings override the system, user and project files")]
     config: String,
+    
+    #[arg(long, value_name = "POLICY", global = true)]
+    #[arg(help = "Handling of secrets and personal data in the prompt: mask, block, local-only or off")]
+    redact: Option<RedactionPolicy>,
+    
+    #[arg(long, global = true)]
+    #[arg(help = "Only use tools with privacy = \"local\"; fails if no local tool can provide consensus")]
+    local_only: bool,
+    
+    #[arg(long, global = true)]
+    #[arg(help = "Leave responses that look like prompt injections out of the consensus")]
+    quarantine: bool,
+    
+    #[arg(long, value_name = "N", global = true, value_parser = clap::value_parser!(u64).range(1..))]
+    #[arg(help = "Run at most N tools at once (overrides max_parallel)")]
+    max_parallel: Option<u64>,
+}
+
+/// Flags that override the selected profile and `AI_CONSENSUS_*` variables.
+#[derive(Args)]
+struct RunArgs {
+    #[arg(short, long, value_delimiter = ',')]
+    #[arg(help = "AI tools to solve the problem (comma-separated)")]
+    solvers: Vec<String>,
+    
+    #[arg(short, long, value_delimiter = ',')]
+    #[arg(help = "AI tools to provide consensus on solver responses, tried in order (comma-separated)")]
+    consensus: Vec<String>,
+    
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    #[arg(help = "Number of consensus tools that each synthesize the responses")]
+    judges: Option<u64>,
+    
+    #[arg(long, value_name = "STRATEGY")]
+    #[arg(help = "How to reconcile several judges: judge (a final pass) or vote")]
+    reconcile: Option<Strategy>,
+    
+    #[arg(long, value_name = "FILE")]
+    #[arg(help = "Scale solver weights by the accuracy in this ai-co eval scores file")]
+    weights_from: Option<String>,
+    
+    #[arg(long)]
+    #[arg(help = "Profile to use (a [profiles.<name>] section); defaults to default_profile")]
+    profile: Option<String>,
+    
+    #[arg(long)]
+    #[arg(help = "How to combine responses: judge, concat, first or vote")]
+    strategy: Option<Strategy>,
+    
+    #[arg(long, value_name = "SECS")]
+    #[arg(help = "Timeout for each tool in seconds")]
+    timeout: Option<u64>,
+    
+    #[arg(long)]
+    #[arg(help = "Output format: text, markdown or json")]
+    format: Option<OutputFormat>,
+}
+
+impl RunArgs {
+    /// Values given explicitly on the command line, layered over the selected profile.
+    fn overrides(&self) -> ProfileConfig {
+        ProfileConfig {
+            solvers: self.solvers.iter().filter(|s| !s.is_empty()).cloned().collect(),
+            consensus: self.consensus.iter().filter(|c| !c.is_empty()).cloned().collect(),
+            judges: self.judges.map(|n| n as usize),
+            reconcile: self.reconcile,
+            weights_from: self.weights_from.clone(),
+            strategy: self.strategy,
+            timeout_secs: self.timeout,
+            format: self.format,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run a named workflow from the configuration
+    Run {
+        #[arg(short, long)]
+        #[arg(help = "Workflow to run (a [workflows.<name>] section)")]
+        workflow: String,
+        
+        #[arg(short, long)]
+        #[arg(help = "Question or problem to solve")]
+        prompt: String,
+    },
+    
+    /// Check that configured tools are installed, allowed and authenticated
+    Doctor {
+        #[arg(help = "Tools to check (default: all configured tools)")]
+        tools: Vec<String>,
+        
+        #[arg(long)]
+        #[arg(help = "Skip the test prompt used for the auth and latency checks")]
+        no_probe: bool,
+    },
+    
+    /// Score solvers and strategies against a dataset of prompts with reference answers
+    Eval(EvalArgs),
+    
+    /// Answer every prompt of a JSONL or CSV file, resuming where an earlier run stopped
+    Batch(BatchArgs),
+    
+    /// Show a saved run, or the diff between two of its responses
+    Show(ShowArgs),
+    
+    /// Find installed AI CLIs and write a starter configuration
+    Init {
+        #[arg(long, value_name = "PATH")]
+        #[arg(help = "Where to write the config (default: ~/.config/ai-consensus-cli/config.toml)")]
+        output: Option<String>,
+        
+        #[arg(short, long)]
+        #[arg(help = "Accept the recommended choices without asking")]
+        yes: bool,
+        
+        #[arg(long)]
+        #[arg(help = "Overwrite an existing file")]
+        force: bool,
+    },
+    
+    /// Inspect the merged configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Args)]
+struct EvalArgs {
+    #[arg(help = "JSONL file with one {\"prompt\": ..., \"expected\": ...} case per line")]
+    dataset: String,
+    
+    #[arg(short, long, value_delimiter = ',')]
+    #[arg(help = "Solvers to evaluate (default: the profile's solvers, else every installed tool)")]
+    solvers: Vec<String>,
+    
+    #[arg(short, long, value_delimiter = ',')]
+    #[arg(help = "Consensus tools for the judge strategy, tried in order (default: the profile's)")]
+    consensus: Vec<String>,
+    
+    #[arg(long)]
+    #[arg(help = "Profile to take solvers, consensus tools and weights from")]
+    profile: Option<String>,
+    
+    #[arg(long, value_delimiter = ',', value_name = "STRATEGIES")]
+    #[arg(help = "Strategies to evaluate (default: judge, vote and first; judge needs a consensus tool)")]
+    strategies: Vec<Strategy>,
+    
+    #[arg(long, value_name = "TOOL")]
+    #[arg(help = "Tool that grades rubric cases (default: the first consensus tool)")]
+    grader: Option<String>,
+    
+    #[arg(long, value_name = "N", default_value_t = 4, value_parser = clap::value_parser!(u64).range(1..))]
+    #[arg(help = "Evaluate at most N cases at once")]
+    concurrency: u64,
+    
+    #[arg(long, value_name = "FILE")]
+    #[arg(help = "Where to write the leaderboard (default: <dataset>.scores.json)")]
+    scores: Option<String>,
+    
+    #[arg(long)]
+    #[arg(help = "Discard the progress of an earlier run instead of resuming it")]
+    restart: bool,
+    
+    #[arg(long, value_name = "SECS")]
+    #[arg(help = "Timeout for each tool in seconds")]
+    timeout: Option<u64>,
+    
+    #[arg(long)]
+    #[arg(help = "Leaderboard format: text, markdown or json")]
+    format: Option<OutputFormat>,
+}
+
+#[derive(Args)]
+struct BatchArgs {
+    #[arg(help = "JSONL file with one {\"id\": ..., \"prompt\": ...} object per line, or a CSV file with a prompt column")]
+    input: String,
+    
+    #[arg(short, long, value_name = "FILE")]
+    #[arg(help = "JSONL file for the results, one line per prompt (default: <input>.results.jsonl)")]
+    out: Option<String>,
+    
+    #[command(flatten)]
+    run: RunArgs,
+    
+    #[arg(long, conflicts_with = "solvers")]
+    #[arg(help = "Pick solvers for each prompt from its topic and tool tags")]
+    auto: bool,
+    
+    #[arg(long)]
+    #[arg(help = "Remove phrases like \"As Claude, ...\" from responses before judging")]
+    strip_identity: bool,
+    
+    #[arg(long)]
+    #[arg(help = "Tag each statement of the consensus with the solvers that support it (judge strategy)")]
+    attribute: bool,
+    
+    #[arg(long, value_name = "N", default_value_t = 4, value_parser = clap::value_parser!(u64).range(1..))]
+    #[arg(help = "Answer at most N prompts at once")]
+    concurrency: u64,
+    
+    #[arg(long)]
+    #[arg(help = "Discard the results of an earlier run instead of resuming it")]
+    restart: bool,
+}
+
+#[derive(Args)]
+struct ShowArgs {
+    #[arg(help = "Run report saved with --format json, or a batch results file")]
+    run: String,
+    
+    #[arg(long)]
+    #[arg(help = "Row to show from a batch results file")]
+    id: Option<String>,
+    
+    #[arg(long, value_name = "A,B", value_parser = parse_pair)]
+    #[arg(help = "Print the diff between two responses (tool names or numbers) or a response and `consensus`")]
+    diff: Option<(String, String)>,
+    
+    #[arg(long, value_name = "BY", default_value = "lines")]
+    #[arg(help = "Compare lines or words with --diff")]
+    diff_by: Granularity,
+    
+    #[arg(long)]
+    #[arg(help = "Output format: text, markdown or json (default: markdown, or text with --diff)")]
+    format: Option<OutputFormat>,
+}
+
+/// Parses `a,b` into its two names.
+fn parse_pair(value: &str) -> std::result::Result<(String, String), String> {
+    match value.split(',').map(str::trim).collect::<Vec<_>>()[..] {
+        [a, b] if !a.is_empty() && !b.is_empty() => Ok((a.to_string(), b.to_string())),
+        _ => Err("expected two names separated by a comma, e.g. q,gemini or q,consensus".to_string()),
+    }
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print the merged configuration and the effective run settings
+    Show {
+        #[arg(long)]
+        #[arg(help = "Annotate each value with the layer it came from")]
+        origin: bool,
+        
+        #[command(flatten)]
+        run: RunArgs,
+    },
+    
+    /// Check configuration files for errors and suspicious settings
+    Check {
+        #[arg(help = "File to check (default: every configuration layer that exists)")]
+        path: Option<String>,
+    },
+    
+    /// Print a JSON Schema for config.toml, for editor completion
+    Schema,
 }
 
 #[tokio::main]
@@ -31,79 +320,645 @@ async fn main() -> Result<()> {
     let cli = Cli::parse();
     info!("Starting AI Consensus CLI");
     
+    // These must work even when the configuration cannot be loaded
+    match &cli.command {
+        Some(Command::Config { action: ConfigAction::Check { path } }) => {
+            check_config(&cli.config, path.as_deref());
+            return Ok(());
+        },
+        Some(Command::Config { action: ConfigAction::Schema }) => {
+            println!("{}", serde_json::to_string_pretty(&schema::config_schema()).unwrap_or_default());
+            return Ok(());
+        },
+        Some(Command::Init { output, yes, force }) => {
+            return run_init(output.as_deref(), *yes, *force).await;
+        },
+        Some(Command::Show(args)) => {
+            return show_run(args);
+        },
+        _ => {},
+    }
+    
     // Load configuration with secure path validation
-    let config = match Config::load(&cli.config) {
-        Ok(config) => config,
+    let layered = match LayeredConfig::load(&cli.config) {
+        Ok(layered) => layered,
         Err(e) => {
             eprintln!("Configuration Error: {}", e);
             eprintln!("\nTo fix this:");
             eprintln!("1. Ensure config.toml exists at ~/.config/ai-consensus-cli/config.toml, or");
             eprintln!("2. Copy config.toml to your current directory, or");
-            eprintln!("3. Use --config /path/to/config.toml to specify the location");
+            eprintln!("3. Use --config /path/to/config.toml to specify the location, or");
+            eprintln!("4. Add a .ai-consensus.toml to your project directory, or");
+            eprintln!("5. Run `ai-co init` to generate one from the AI CLIs you have installed");
+            eprintln!("\nRun `ai-co config check` for line-by-line diagnostics.");
             eprintln!("\nExample config.toml can be found at:");
             eprintln!("https://github.com/your-repo/ai-consensus-cli/blob/main/config.toml");
             return Err(e);
         }
     };
     
-    // Show help if requested (simplified check)
-    if cli.solvers.is_empty() || cli.consensus.is_empty() || cli.prompt.is_empty() {
-        println!("{}", config.generate_help_text());
+    match &cli.command {
+        Some(Command::Run { workflow, prompt }) => {
+            let mut config = layered.config;
+            config.injection.quarantine |= cli.quarantine;
+            if let Some(limit) = cli.max_parallel {
+                config.max_parallel = Some(limit as usize);
+            }
+            let policy = cli.redact.unwrap_or(config.redaction.policy);
+            return run_workflow(&config, workflow, prompt, policy, cli.local_only).await;
+        },
+        Some(Command::Doctor { tools, no_probe }) => {
+            let mut config = layered.config;
+            if let Some(limit) = cli.max_parallel {
+                config.max_parallel = Some(limit as usize);
+            }
+            return run_doctor(&config, tools, !*no_probe).await;
+        },
+        Some(Command::Eval(args)) => {
+            return run_eval(layered, args, &cli).await;
+        },
+        Some(Command::Batch(args)) => {
+            return run_batch(layered, args, &cli).await;
+        },
+        Some(Command::Config { action: ConfigAction::Show { origin, run } }) => {
+            show_config(&layered, *origin, run);
+            return Ok(());
+        },
+        _ => {},
+    }
+    
+    // Show help if no question was asked
+    if cli.prompt.is_empty() {
+        println!("{}", layered.config.generate_help_text());
         std::process::exit(0);
     }
     
-    // Validate tools and prompt with security checks
-    if let Err(e) = Validator::validate_tools(&cli.solvers, &cli.consensus, &config) {
+    let settings = match layered.run_settings(cli.run.profile.as_deref(), &cli.run.overrides()) {
+        Ok(run_settings) => run_settings.settings,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let mut config = layered.config;
+    let format = settings.format.unwrap_or_default();
+    ui::set_quiet(format != OutputFormat::Text);
+    
+    if let Some(timeout) = settings.timeout_secs {
+        config.set_timeout(timeout);
+    }
+    if let Some(limit) = cli.max_parallel {
+        config.max_parallel = Some(limit as usize);
+    }
+    
+    let mut options = match RunOptions::new(&settings, &config) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    options.policy = cli.redact.unwrap_or(options.policy);
+    options.auto = cli.auto;
+    options.local_only = cli.local_only;
+    options.quarantine |= cli.quarantine;
+    options.seed = cli.seed;
+    options.strip_identity |= cli.strip_identity;
+    options.attribute |= cli.attribute;
+    if let Err(e) = options.validate(&config) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+    if cli.diff.is_some() && format == OutputFormat::Json {
+        eprintln!("Error: --diff cannot be added to JSON output; save the report and use `ai-co show <report> --diff a,b`");
+        std::process::exit(1);
+    }
+    
+    if cli.dry_run {
+        match pipeline::dry_run(&cli.prompt, &options, &config).await {
+            Ok(plan) => println!("{}", plan.render(format)),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+    
+    let scheduler = Scheduler::new(config.max_parallel, config.tools.values());
+    let answered = if cli.tui {
+        run_dashboard(cli.prompt.clone(), options, config, scheduler).await
+    } else {
+        pipeline::answer(&cli.prompt, &options, &config, &scheduler).await
+    };
+    let report = match answered {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    println!("{}", report.render(format));
+    if let Some((old, new)) = &cli.diff {
+        match render_diff(&report, old, new, cli.diff_by, format) {
+            Ok(diff) => println!("\n{}", diff),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    
+    info!("AI Consensus CLI completed successfully");
+    Ok(())
+}
+
+/// Answers the prompt while the `--tui` dashboard shows the progress; the report is returned
+/// once the user closes it.
+async fn run_dashboard(prompt: String, options: RunOptions, config: Config, scheduler: Scheduler) -> Result<RunReport> {
+    if !std::io::IsTerminal::is_terminal(&std::io::stdout()) {
+        eprintln!("Error: --tui needs a terminal");
+        std::process::exit(1);
+    }
+    ui::set_quiet(true);
+    let (sender, events) = tokio::sync::mpsc::unbounded_channel();
+    progress::set_sink(Some(sender));
+    let (done, finished) = tokio::sync::oneshot::channel();
+    let run = tokio::spawn(async move {
+        let answered = pipeline::answer(&prompt, &options, &config, &scheduler).await;
+        let _ = done.send(answered.as_ref().map(|_| ()).map_err(|e| e.to_string()));
+        answered
+    });
+    
+    let exit = tokio::task::spawn_blocking(move || dashboard::run(events, finished)).await;
+    progress::set_sink(None);
+    let (message, code) = match exit {
+        Ok(Ok(Exit::Closed)) => return run.await.map_err(|e| CliError::ConfigError { message: format!("Task join error: {}", e) })?,
+        Ok(Ok(Exit::Aborted)) => ("Run stopped".to_string(), 130),
+        Ok(Err(e)) => (format!("Error: cannot show the dashboard: {}", e), 1),
+        Err(e) => (format!("Error: the dashboard failed: {}", e), 1),
+    };
+    stop(run).await;
+    eprintln!("{}", message);
+    std::process::exit(code);
+}
+
+/// Aborts `run` and waits until the tools it started are killed, since exiting the process
+/// skips the destructors that would kill them.
+async fn stop<T>(run: tokio::task::JoinHandle<T>) {
+    run.abort();
+    let _ = run.await;
+    if !ToolManager::wait_for_tools(std::time::Duration::from_secs(5)).await {
+        warn!("Some tools were still running when the run was stopped");
+    }
+}
+
+fn show_run(args: &ShowArgs) -> Result<()> {
+    let report = match RunReport::load(&args.run, args.id.as_deref()) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let Some((old, new)) = &args.diff else {
+        println!("{}", report.render(args.format.unwrap_or(OutputFormat::Markdown)));
+        return Ok(());
+    };
+    match render_diff(&report, old, new, args.diff_by, args.format.unwrap_or_default()) {
+        Ok(diff) => println!("{}", diff),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+/// The diff between two responses of `report`: coloured or side by side on a terminal,
+/// otherwise unified text, in a fenced block for Markdown or a JSON object.
+fn render_diff(report: &RunReport, old: &str, new: &str, by: Granularity, format: OutputFormat) -> Result<String> {
+    match format {
+        OutputFormat::Text => report.diff(old, new, by, Layout::detect()),
+        OutputFormat::Markdown => Ok(format!("```diff\n{}\n```", report.diff(old, new, by, Layout::Unified)?)),
+        OutputFormat::Json => {
+            let diff = report.diff(old, new, by, Layout::Unified)?;
+            let by = if by == Granularity::Words { "words" } else { "lines" };
+            Ok(serde_json::to_string_pretty(&serde_json::json!({ "from": old, "to": new, "by": by, "diff": diff })).unwrap_or_default())
+        },
+    }
+}
+
+async fn run_eval(layered: LayeredConfig, args: &EvalArgs, cli: &Cli) -> Result<()> {
+    let fail = |e: ai_consensus_cli::CliError| -> ! {
         eprintln!("Error: {}", e);
         std::process::exit(1);
+    };
+    let overrides = ProfileConfig {
+        solvers: args.solvers.iter().filter(|s| !s.is_empty()).cloned().collect(),
+        consensus: args.consensus.iter().filter(|c| !c.is_empty()).cloned().collect(),
+        timeout_secs: args.timeout,
+        format: args.format,
+        ..Default::default()
+    };
+    let settings = layered.run_settings(args.profile.as_deref(), &overrides).unwrap_or_else(|e| fail(e)).settings;
+    let mut config = layered.config;
+    if let Some(timeout) = settings.timeout_secs {
+        config.set_timeout(timeout);
+    }
+    if let Some(limit) = cli.max_parallel {
+        config.max_parallel = Some(limit as usize);
+    }
+    let format = settings.format.unwrap_or_default();
+    ui::set_quiet(format != OutputFormat::Text);
+    
+    let cases = Evaluator::load_dataset(&args.dataset).unwrap_or_else(|e| fail(e));
+    let solvers = if settings.solvers.is_empty() {
+        let mut installed: Vec<String> = config.tools.iter().filter(|(_, tool)| ToolManager::is_available(tool)).map(|(key, _)| key.clone()).collect();
+        installed.sort();
+        installed
+    } else {
+        settings.solvers.clone()
+    };
+    if solvers.is_empty() {
+        eprintln!("Error: No solvers to evaluate - use -s or install one of the configured tools");
+        std::process::exit(1);
+    }
+    let strategies = if !args.strategies.is_empty() {
+        args.strategies.clone()
+    } else if settings.consensus.is_empty() {
+        vec![Strategy::Vote, Strategy::First]
+    } else {
+        vec![Strategy::Judge, Strategy::Vote, Strategy::First]
+    };
+    if strategies.contains(&Strategy::Judge) && settings.consensus.is_empty() {
+        eprintln!("Error: The judge strategy needs a consensus tool - use -c or a profile with a consensus tool");
+        std::process::exit(1);
+    }
+    let grader = args.grader.clone().or_else(|| settings.consensus.first().cloned());
+    if grader.is_none() && cases.iter().any(|case| case.scorer == Scorer::Rubric) {
+        eprintln!("Error: Rubric cases need a grader - use --grader or -c");
+        std::process::exit(1);
+    }
+    let mut tools = settings.consensus.clone();
+    tools.extend(grader.clone());
+    if let Err(e) = Validator::validate_tools(&solvers, &tools, &config) {
+        fail(e);
+    }
+    
+    // Tools that are not installed would only fail every case
+    let available = |key: &String| ToolManager::is_available(&config.tools[key]);
+    let mut unavailable: Vec<String> = solvers.iter().chain(&tools).filter(|key| !available(key)).cloned().collect();
+    unavailable.sort();
+    unavailable.dedup();
+    if !unavailable.is_empty() {
+        ui::show_unavailable(&unavailable);
+    }
+    let solvers: Vec<String> = solvers.into_iter().filter(available).collect();
+    let consensus: Vec<String> = settings.consensus.iter().filter(|key| available(key)).cloned().collect();
+    if solvers.is_empty() {
+        eprintln!("Error: None of the solvers to evaluate is available");
+        std::process::exit(1);
     }
     
-    if let Err(e) = Validator::sanitize_prompt(&cli.prompt) {
+    // Cloud tools would only fail every case
+    let (solvers, consensus) = if cli.local_only {
+        let run = privacy::restrict_to_local(&solvers, &consensus, &config).unwrap_or_else(|e| fail(e));
+        ui::show_local_only(&run);
+        (run.solvers, run.consensus)
+    } else {
+        (solvers, consensus)
+    };
+    let local_grader = |key: &String| !cli.local_only || config.tools[key].is_local();
+    if grader.as_ref().is_some_and(|key| !local_grader(key)) && cases.iter().any(|case| case.scorer == Scorer::Rubric) {
+        eprintln!("Error: The grader of the rubric cases is a cloud tool - use --grader with a local tool");
+        std::process::exit(1);
+    }
+    if strategies.contains(&Strategy::Judge) && consensus.is_empty() {
+        eprintln!("Error: No consensus tool for the judge strategy is available");
+        std::process::exit(1);
+    }
+    if grader.as_ref().is_some_and(|key| !available(key)) && cases.iter().any(|case| case.scorer == Scorer::Rubric) {
+        eprintln!("Error: The grader of the rubric cases is not available");
+        std::process::exit(1);
+    }
+    
+    let plan = EvalPlan {
+        consensus: consensus.iter().map(|key| (key.clone(), config.tools[key].clone())).collect(),
+        judges: settings.judges.unwrap_or(1),
+        reconcile: settings.reconcile.unwrap_or_default(),
+        weights: weights::resolve(&config, &settings).unwrap_or_else(|e| fail(e)),
+        grader: grader.filter(local_grader).map(|key| (key.clone(), config.tools[&key].clone())),
+        policy: cli.redact.unwrap_or(config.redaction.policy),
+        local_only: cli.local_only,
+        quarantine: cli.quarantine || config.injection.quarantine,
+        concurrency: args.concurrency as usize,
+        solvers,
+        strategies,
+    };
+    
+    // Finished cases are kept next to the dataset, so an interrupted run can resume
+    let dataset = std::path::Path::new(&args.dataset);
+    let progress = dataset.with_extension("progress.jsonl");
+    if args.restart {
+        let _ = std::fs::remove_file(&progress);
+    }
+    let done = Evaluator::load_progress(&progress);
+    let results = Evaluator::run(&cases, &plan, &config, &progress, done).await.unwrap_or_else(|e| fail(e));
+    
+    let leaderboard = Evaluator::leaderboard(&results);
+    let scores = args.scores.clone().map(std::path::PathBuf::from).unwrap_or_else(|| dataset.with_extension("scores.json"));
+    if let Err(e) = std::fs::write(&scores, serde_json::to_string_pretty(&leaderboard).unwrap_or_default()) {
+        eprintln!("Error: Cannot write {}: {}", scores.display(), e);
+        std::process::exit(1);
+    }
+    ui::show_eval_done(&scores.display().to_string());
+    println!("{}", leaderboard.render(format));
+    Ok(())
+}
+
+async fn run_batch(layered: LayeredConfig, args: &BatchArgs, cli: &Cli) -> Result<()> {
+    let fail = |e: ai_consensus_cli::CliError| -> ! {
         eprintln!("Error: {}", e);
         std::process::exit(1);
+    };
+    let settings = layered.run_settings(args.run.profile.as_deref(), &args.run.overrides()).unwrap_or_else(|e| fail(e)).settings;
+    let mut config = layered.config;
+    if let Some(timeout) = settings.timeout_secs {
+        config.set_timeout(timeout);
     }
+    if let Some(limit) = cli.max_parallel {
+        config.max_parallel = Some(limit as usize);
+    }
+    let format = settings.format.unwrap_or_default();
+    ui::set_quiet(format != OutputFormat::Text);
+    ui::set_batch(true);
     
-    // Check tool availability
-    let (available_solvers, unavailable_tools) = ToolManager::check_availability(&cli.solvers, &config);
+    let mut options = RunOptions::new(&settings, &config).unwrap_or_else(|e| fail(e));
+    options.policy = cli.redact.unwrap_or(options.policy);
+    options.auto = args.auto;
+    options.local_only = cli.local_only;
+    options.quarantine |= cli.quarantine;
+    options.strip_identity |= args.strip_identity;
+    options.attribute |= args.attribute;
+    if let Err(e) = options.validate(&config) {
+        fail(e);
+    }
     
-    if !config.tools.contains_key(&cli.consensus) {
-        eprintln!("Error: ToolNotFound - consensus tool '{}' not found", cli.consensus);
+    let rows = Batch::load(&args.input).unwrap_or_else(|e| fail(e));
+    let input = std::path::Path::new(&args.input);
+    let out = args.out.clone().map(std::path::PathBuf::from).unwrap_or_else(|| input.with_extension("results.jsonl"));
+    if out == input {
+        eprintln!("Error: The results file must not be the input file");
         std::process::exit(1);
     }
+    if args.restart {
+        let _ = std::fs::remove_file(&out);
+    }
+    let summary = Batch::run(&rows, &options, &config, &out, args.concurrency as usize).await.unwrap_or_else(|e| fail(e));
+    ui::show_batch_done(&out.display().to_string());
+    println!("{}", summary.render(format));
+    if !summary.failed.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+async fn run_doctor(config: &Config, tools: &[String], probe: bool) -> Result<()> {
+    for tool in tools {
+        if !config.tools.contains_key(tool) {
+            eprintln!("Error: {}", ai_consensus_cli::CliError::ToolNotFound { tool: tool.clone() });
+            std::process::exit(1);
+        }
+    }
     
-    let consensus_tool = &config.tools[&cli.consensus];
-    if !ToolManager::is_available(consensus_tool) {
-        eprintln!("Error: ToolNotFound - consensus tool '{}' not available", cli.consensus);
+    if probe {
+        println!("🩺 Checking {} tool(s), sending a short test prompt to each...\n", if tools.is_empty() { config.tools.len() } else { tools.len() });
+    }
+    
+    let results = Doctor::run(config, tools, probe).await;
+    println!("{}", Doctor::render_table(&results));
+    
+    if results.iter().any(|d| !d.is_healthy()) {
         std::process::exit(1);
     }
+    Ok(())
+}
+
+/// Asks a question on the terminal; an empty answer (or end of input) picks `default`.
+fn ask(question: &str, default: &str) -> String {
+    print!("{} [{}] ", question, default);
+    let _ = std::io::Write::flush(&mut std::io::stdout());
     
-    if !unavailable_tools.is_empty() {
-        warn!("Unavailable tools: {}", unavailable_tools.join(", "));
-        println!("❌ Unavailable tools: {} (skipping)", unavailable_tools.join(", "));
+    let mut answer = String::new();
+    match std::io::stdin().read_line(&mut answer) {
+        Ok(n) if n > 0 && !answer.trim().is_empty() => answer.trim().to_string(),
+        _ => default.to_string(),
+    }
+}
+
+async fn run_init(output: Option<&str>, yes: bool, force: bool) -> Result<()> {
+    let path = output.map(|p| p.to_string()).unwrap_or_else(layers::user_config_path);
+    if std::path::Path::new(&path).exists() && !force {
+        eprintln!("Error: {} already exists - use --force to overwrite it", path);
+        std::process::exit(1);
     }
+    let interactive = !yes && std::io::IsTerminal::is_terminal(&std::io::stdin());
     
-    if available_solvers.is_empty() {
-        eprintln!("Error: All solvers failed - no available tools");
+    println!("🔎 Looking for AI CLIs on PATH...");
+    let discovered = Initializer::discover().await;
+    if discovered.is_empty() {
+        eprintln!("Error: none of the supported AI CLIs were found on PATH");
+        eprintln!("Install one (for example claude, gemini, q or ollama) and run `ai-co init` again.");
         std::process::exit(1);
     }
+    for tool in &discovered {
+        println!("  ✅ {:<8} {} ({})", tool.known.command, tool.version.as_deref().unwrap_or("(unknown version)"), tool.path.display());
+    }
+    println!();
     
-    // Run solvers
-    let responses = ToolManager::run_solvers(&available_solvers, &cli.prompt).await?;
+    let mut selections = Vec::new();
+    for tool in &discovered {
+        let known = tool.known;
+        if interactive && !ask(&format!("Include {} ({})?", known.name, known.command), "y").to_lowercase().starts_with('y') {
+            continue;
+        }
+        
+        if known.key == "ollama" {
+            let models = Initializer::ollama_models().await;
+            let chosen: Vec<&String> = if models.is_empty() {
+                Vec::new()
+            } else if interactive {
+                println!("  Local models:");
+                for (i, model) in models.iter().enumerate() {
+                    println!("    {}) {}", i + 1, model);
+                }
+                let answer = ask("  Which models? (comma-separated numbers)", "all");
+                if answer == "all" {
+                    models.iter().collect()
+                } else {
+                    answer.split(',').filter_map(|n| n.trim().parse::<usize>().ok()).filter_map(|n| models.get(n.wrapping_sub(1))).collect()
+                }
+            } else {
+                models.iter().collect()
+            };
+            
+            if chosen.is_empty() {
+                selections.push(Selection::new(tool, 0));
+            }
+            for (i, model) in chosen.into_iter().enumerate() {
+                selections.push(Selection::ollama(tool, model, i == 0));
+            }
+            continue;
+        }
+        
+        let mut template = 0;
+        if interactive && known.templates.len() > 1 {
+            println!("  Argument templates for {}:", known.command);
+            for (i, args) in known.templates.iter().enumerate() {
+                println!("    {}) {}", i + 1, args.join(" "));
+            }
+            template = ask("  Which one?", "1").parse::<usize>().unwrap_or(1).saturating_sub(1);
+        }
+        selections.push(Selection::new(tool, template));
+    }
     
-    // Get consensus
-    ui::show_consensus_start();
+    if selections.is_empty() {
+        eprintln!("Error: no tools selected, nothing written");
+        std::process::exit(1);
+    }
     
-    let consensus_start = std::time::Instant::now();
-    let consensus = ConsensusEngine::get_consensus(consensus_tool, responses, &cli.prompt).await?;
-    let consensus_time = consensus_start.elapsed();
+    let content = Initializer::render(&selections);
+    if let Some(parent) = std::path::Path::new(&path).parent() {
+        if let Err(e) = std::fs::create_dir_all(parent) {
+            eprintln!("Error: could not create {}: {}", parent.display(), e);
+            std::process::exit(1);
+        }
+    }
+    if let Err(e) = std::fs::write(&path, content) {
+        eprintln!("Error: could not write {}: {}", path, e);
+        std::process::exit(1);
+    }
     
-    ui::show_consensus_complete(consensus_time.as_secs_f32());
+    println!("✅ Wrote {} tool(s) to {}", selections.len(), path);
+    println!("Next: run `ai-co doctor` to check authentication, then `ai-co -p \"Your question\"`");
+    Ok(())
+}
+
+fn check_config(config_path: &str, path: Option<&str>) {
+    let paths: Vec<String> = match path {
+        Some(path) => vec![path.to_string()],
+        None => layers::layer_files(config_path).into_iter().map(|(_, path)| path).collect(),
+    };
+    
+    if paths.is_empty() {
+        eprintln!("Error: no configuration file found (looked for {})", config_path);
+        std::process::exit(1);
+    }
     
-    // Clean output - remove ANSI codes and extra formatting
-    let clean_consensus = Validator::strip_ansi_codes(&consensus);
-    println!("{}", clean_consensus.trim());
+    let mut files = Vec::new();
+    for path in &paths {
+        match std::fs::read_to_string(path) {
+            Ok(content) => files.push((path.clone(), content)),
+            Err(e) => {
+                eprintln!("Error: could not read {}: {}", path, e);
+                std::process::exit(1);
+            }
+        }
+    }
     
-    info!("AI Consensus CLI completed successfully");
+    println!("🔍 Checking {}", paths.join(", "));
+    let diagnostics = ConfigChecker::check(&files);
+    for diagnostic in &diagnostics {
+        println!("{}", diagnostic);
+    }
+    
+    let errors = diagnostics.iter().filter(|d| d.severity == Severity::Error).count();
+    let warnings = diagnostics.len() - errors;
+    if diagnostics.is_empty() {
+        println!("✅ No problems found");
+    } else {
+        println!("\n{} error(s), {} warning(s)", errors, warnings);
+    }
+    
+    if errors > 0 {
+        std::process::exit(1);
+    }
+}
+
+fn show_config(layered: &LayeredConfig, origin: bool, run: &RunArgs) {
+    if origin {
+        println!("# Layers (lowest precedence first):");
+        for layer in &layered.layers {
+            println!("#   {}", layer.label());
+        }
+        println!();
+    }
+    println!("{}", layered.describe(origin));
+    
+    match layered.run_settings(run.profile.as_deref(), &run.overrides()) {
+        Ok(settings) => {
+            let described = settings.describe(origin);
+            if !described.is_empty() {
+                println!("\n# Effective run settings");
+                println!("{}", described);
+            }
+        },
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+async fn run_workflow(config: &Config, workflow: &str, prompt: &str, policy: RedactionPolicy, local_only: bool) -> Result<()> {
+    if let Err(e) = Validator::sanitize_prompt(prompt) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+    
+    // Stage tools cannot be swapped, so a local-only run needs every one of them to be local
+    let mut tools: Vec<String> = config.workflows.get(workflow)
+        .map(|w| w.stages.iter().flat_map(|s| s.tools.iter().chain(s.consensus.iter())).cloned().collect())
+        .unwrap_or_default();
+    tools.sort();
+    tools.dedup();
+    
+    let guarded = Validator::redact(prompt, &config.redaction)
+        .and_then(|redaction| {
+            if local_only || (policy == RedactionPolicy::LocalOnly && !redaction.is_empty()) {
+                privacy::require_local(&tools, config)?;
+            }
+            let guarded = redaction::guard(policy, prompt, &redaction, &tools, &[], config)?;
+            Ok((redaction, guarded))
+        });
+    let (redaction, guarded) = match guarded {
+        Ok(guarded) => guarded,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if !redaction.is_empty() {
+        ui::show_redactions(policy, &redaction.kinds());
+    }
+    
+    let mut config = config.clone();
+    if local_only || guarded.restricted.is_some() {
+        privacy::drop_cloud_fallbacks(&mut config);
+    }
+    let output = match WorkflowRunner::run(&config, workflow, &guarded.prompt).await {
+        Ok(output) => output,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    
+    println!();
+    println!("{}", Validator::strip_ansi_codes(&redaction.restore(&output)).trim());
+    
+    info!("Workflow {} completed successfully", workflow);
     Ok(())
 }
 
@@ -114,9 +969,170 @@ mod tests {
     #[test]
     fn test_cli_parsing() {
         // Test that CLI parsing works with the new structure
-        let cli = Cli::parse_from(&["ai-co", "-s", "test", "-c", "test", "-p", "test prompt"]);
-        assert_eq!(cli.solvers, vec!["test"]);
-        assert_eq!(cli.consensus, "test");
+        let cli = Cli::parse_from(["ai-co", "-s", "test", "-c", "test", "-p", "test prompt"]);
+        assert_eq!(cli.run.solvers, vec!["test"]);
+        assert_eq!(cli.run.consensus, vec!["test"]);
         assert_eq!(cli.prompt, "test prompt");
     }
+
+    #[test]
+    fn test_auto_flag_parsing() {
+        let cli = Cli::parse_from(["ai-co", "--auto", "-c", "test", "-p", "test prompt"]);
+        assert!(cli.auto);
+        assert!(cli.run.solvers.is_empty());
+        
+        assert!(Cli::try_parse_from(["ai-co", "--auto", "-s", "q", "-c", "test", "-p", "x"]).is_err());
+    }
+
+    #[test]
+    fn test_profile_overrides() {
+        let cli = Cli::parse_from(["ai-co", "--profile", "quick", "-c", "claude", "--timeout", "30", "-p", "test"]);
+        assert_eq!(cli.run.profile.as_deref(), Some("quick"));
+        
+        let profile = ProfileConfig {
+            solvers: vec!["q".to_string(), "gemini".to_string()],
+            consensus: vec!["q".to_string()],
+            strategy: Some(Strategy::Vote),
+            ..Default::default()
+        };
+        let merged = profile.overridden_by(&cli.run.overrides());
+        assert_eq!(merged.solvers, vec!["q", "gemini"]);
+        assert_eq!(merged.consensus, vec!["claude"]);
+        assert_eq!(merged.strategy, Some(Strategy::Vote));
+        assert_eq!(merged.timeout_secs, Some(30));
+    }
+
+    #[test]
+    fn test_local_only_parsing() {
+        let cli = Cli::parse_from(["ai-co", "--local-only", "-s", "ollama", "-c", "claude", "-p", "test"]);
+        assert!(cli.local_only);
+        
+        let cli = Cli::parse_from(["ai-co", "run", "--workflow", "review", "--local-only", "-p", "test"]);
+        assert!(cli.local_only);
+    }
+
+    #[test]
+    fn test_judges_parsing() {
+        let cli = Cli::parse_from(["ai-co", "-s", "q", "-c", "claude,gemini,q", "--judges", "2", "--reconcile", "vote", "-p", "test"]);
+        assert_eq!(cli.run.consensus, vec!["claude", "gemini", "q"]);
+        let overrides = cli.run.overrides();
+        assert_eq!(overrides.judges, Some(2));
+        assert_eq!(overrides.reconcile, Some(Strategy::Vote));
+        
+        assert!(Cli::try_parse_from(["ai-co", "--judges", "0", "-s", "q", "-p", "test"]).is_err());
+    }
+
+    #[test]
+    fn test_seed_parsing() {
+        let cli = Cli::parse_from(["ai-co", "-s", "q,gemini", "-c", "claude", "--seed", "42", "--strip-identity", "-p", "test"]);
+        assert_eq!(cli.seed, Some(42));
+        assert!(cli.strip_identity);
+        assert!(!cli.attribute);
+        assert!(Cli::parse_from(["ai-co", "-s", "q", "--attribute", "-p", "test"]).attribute);
+        assert!(!cli.dry_run);
+        assert!(Cli::parse_from(["ai-co", "-s", "q", "--dry-run", "-p", "test"]).dry_run);
+        assert!(!cli.tui);
+        assert!(Cli::parse_from(["ai-co", "-s", "q", "--tui", "-p", "test"]).tui);
+        assert!(Cli::try_parse_from(["ai-co", "-s", "q", "--tui", "--dry-run", "-p", "test"]).is_err());
+        
+        let cli = Cli::parse_from(["ai-co", "-s", "q,gemini", "--diff", "q,consensus", "--diff-by", "words", "-p", "test"]);
+        assert_eq!(cli.diff, Some(("q".to_string(), "consensus".to_string())));
+        assert_eq!(cli.diff_by, Granularity::Words);
+        assert!(Cli::try_parse_from(["ai-co", "-s", "q", "--diff", "q", "-p", "test"]).is_err());
+        
+        let cli = Cli::parse_from(["ai-co", "show", "results.jsonl", "--id", "fr", "--diff", "1,2"]);
+        match cli.command {
+            Some(Command::Show(args)) => {
+                assert_eq!(args.run, "results.jsonl");
+                assert_eq!(args.id.as_deref(), Some("fr"));
+                assert_eq!(args.diff, Some(("1".to_string(), "2".to_string())));
+                assert_eq!(args.diff_by, Granularity::Lines);
+            },
+            _ => panic!("expected the show subcommand"),
+        }
+        
+        assert!(Cli::try_parse_from(["ai-co", "-s", "q", "--seed", "-1", "-p", "test"]).is_err());
+    }
+
+    #[test]
+    fn test_max_parallel_parsing() {
+        let cli = Cli::parse_from(["ai-co", "--max-parallel", "2", "-s", "q,ollama", "-c", "claude", "-p", "test"]);
+        assert_eq!(cli.max_parallel, Some(2));
+        
+        assert!(Cli::try_parse_from(["ai-co", "--max-parallel", "0", "-s", "q", "-p", "test"]).is_err());
+    }
+
+    #[test]
+    fn test_global_flags_before_subcommand() {
+        let cli = Cli::parse_from(["ai-co", "--config", "team.toml", "--max-parallel", "2", "doctor"]);
+        assert_eq!(cli.config, "team.toml");
+        assert_eq!(cli.max_parallel, Some(2));
+        assert!(matches!(cli.command, Some(Command::Doctor { .. })));
+        
+        let cli = Cli::parse_from(["ai-co", "--config", "team.toml", "--local-only", "eval", "cases.jsonl", "--redact", "block"]);
+        assert_eq!(cli.config, "team.toml");
+        assert!(cli.local_only);
+        assert_eq!(cli.redact, Some(RedactionPolicy::Block));
+        assert!(matches!(cli.command, Some(Command::Eval(_))));
+    }
+
+    #[test]
+    fn test_init_subcommand_parsing() {
+        let cli = Cli::parse_from(["ai-co", "init", "--yes", "--output", "my.toml"]);
+        match cli.command {
+            Some(Command::Init { output, yes, force }) => {
+                assert_eq!(output.as_deref(), Some("my.toml"));
+                assert!(yes);
+                assert!(!force);
+            },
+            _ => panic!("expected init subcommand"),
+        }
+    }
+
+    #[test]
+    fn test_run_subcommand_parsing() {
+        let cli = Cli::parse_from(["ai-co", "run", "--workflow", "review", "-p", "test prompt"]);
+        match cli.command {
+            Some(Command::Run { workflow, prompt }) => {
+                assert_eq!(workflow, "review");
+                assert_eq!(prompt, "test prompt");
+            },
+            _ => panic!("expected run subcommand"),
+        }
+    }
+
+    #[test]
+    fn test_eval_subcommand_parsing() {
+        let cli = Cli::parse_from(["ai-co", "eval", "cases.jsonl", "-s", "q,gemini", "--strategies", "vote,judge", "--max-parallel", "2"]);
+        assert_eq!(cli.max_parallel, Some(2));
+        match cli.command {
+            Some(Command::Eval(args)) => {
+                assert_eq!(args.dataset, "cases.jsonl");
+                assert_eq!(args.solvers, vec!["q", "gemini"]);
+                assert_eq!(args.strategies, vec![Strategy::Vote, Strategy::Judge]);
+                assert_eq!(args.concurrency, 4);
+                assert!(!args.restart);
+            },
+            _ => panic!("expected eval subcommand"),
+        }
+        assert!(Cli::try_parse_from(["ai-co", "eval", "cases.jsonl", "--concurrency", "0"]).is_err());
+    }
+    
+    #[test]
+    fn test_batch_subcommand_parsing() {
+        let cli = Cli::parse_from(["ai-co", "batch", "prompts.csv", "--out", "results.jsonl", "-s", "q,gemini", "--strategy", "vote", "--local-only", "--concurrency", "8"]);
+        assert!(cli.local_only);
+        match cli.command {
+            Some(Command::Batch(args)) => {
+                assert_eq!(args.input, "prompts.csv");
+                assert_eq!(args.out.as_deref(), Some("results.jsonl"));
+                assert_eq!(args.run.overrides().solvers, vec!["q", "gemini"]);
+                assert_eq!(args.run.strategy, Some(Strategy::Vote));
+                assert_eq!(args.concurrency, 8);
+                assert!(!args.auto && !args.restart);
+            },
+            _ => panic!("expected batch subcommand"),
+        }
+        assert!(Cli::try_parse_from(["ai-co", "batch", "prompts.jsonl", "--auto", "-s", "q"]).is_err());
+    }
 }
@@ -0,0 +1,199 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use crate::{CliError, Result, Validator, diff::{self, Granularity, Layout}, attribution::Attribution, injection::Flagged, presentation::Presented, scheduler::SolverTiming, tools::Substitution};
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Progress indicators followed by the plain consensus answer.
+    #[default]
+    Text,
+    /// Consensus and individual responses as a Markdown document.
+    Markdown,
+    /// A single JSON object, suitable for scripts.
+    Json,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(CliError::ConfigError {
+                message: format!("Unknown output format '{}' (expected text, markdown or json)", s)
+            }),
+        }
+    }
+}
+
+/// Everything produced by one solver/consensus run.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct RunReport {
+    pub prompt: String,
+    pub solvers: Vec<String>,
+    /// The consensus tool that produced the answer; with several judges, the one that reconciled them.
+    pub consensus_tool: Option<String>,
+    /// Consensus tools whose syntheses were reconciled.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub judges: Vec<String>,
+    pub strategy: String,
+    pub consensus: String,
+    pub responses: Vec<String>,
+    /// The tool that wrote each response.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub answered: Vec<String>,
+    /// Kinds of sensitive data masked before the prompt was sent.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub redacted: Vec<String>,
+    /// Responses that look like prompt injections.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub flagged: Vec<Flagged>,
+    /// Order in which the judge saw the responses.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub presentation: Vec<Presented>,
+    /// Seed of that order; pass it to `--seed` to reproduce it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Statements of the consensus and the solvers they cite, with `--attribute`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attribution: Option<Attribution>,
+    /// Weight of each answering solver, when they differ.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub weights: BTreeMap<String, f64>,
+    /// Queue wait and run time of each solver.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub timings: Vec<SolverTiming>,
+    /// Solvers answered for by a fallback tool.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub substitutions: Vec<Substitution>,
+}
+
+impl RunReport {
+    /// Reads a report saved with `--format json`, or the row `id` of a batch results file.
+    pub fn load(path: &str, id: Option<&str>) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| CliError::ConfigError {
+            message: format!("Cannot read run {}: {}", path, e)
+        })?;
+        let invalid = |reason: String| CliError::ConfigError { message: format!("{}: {}", path, reason) };
+        // A pretty-printed report spans many lines; a results file has one run per line
+        let runs: Vec<serde_json::Value> = match serde_json::from_str(&content) {
+            Ok(run) => vec![run],
+            Err(_) => content.lines()
+                .filter(|line| !line.trim().is_empty())
+                .enumerate()
+                .map(|(i, line)| serde_json::from_str(line).map_err(|e| invalid(format!("line {}: {}", i + 1, e))))
+                .collect::<Result<_>>()?,
+        };
+        let run = match id {
+            Some(id) => runs.into_iter().rev().find(|run| run.get("id").and_then(|v| v.as_str()) == Some(id))
+                .ok_or_else(|| invalid(format!("no run with id '{}'", id)))?,
+            None if runs.len() == 1 => runs.into_iter().next().unwrap_or_default(),
+            None => return Err(invalid(format!("{} runs in this file; pick one with --id", runs.len()))),
+        };
+        if let Some(error) = run.get("error").and_then(|e| e.as_str()) {
+            return Err(invalid(format!("this run failed: {}", error)));
+        }
+        serde_json::from_value(run).map_err(|e| invalid(e.to_string()))
+    }
+
+    /// A label and the text of the response or answer called `name`: `consensus`, the
+    /// 1-based number of a response, or the tool that wrote it (or the solver it answered for).
+    pub fn pick(&self, name: &str) -> Result<(String, String)> {
+        if name == "consensus" {
+            let label = self.consensus_tool.as_ref().map(|tool| format!("consensus ({})", tool)).unwrap_or_else(|| "consensus".to_string());
+            return Ok((label, Validator::strip_ansi_codes(&self.consensus)));
+        }
+        let tool = self.substitutions.iter().find(|s| s.solver == name).map(|s| s.replaced_by.as_str()).unwrap_or(name);
+        let position = match name.parse::<usize>() {
+            Ok(number) => number.checked_sub(1).filter(|i| *i < self.responses.len()),
+            Err(_) => self.answered.iter().position(|answered| answered == tool),
+        };
+        let Some(i) = position else {
+            let mut names: Vec<String> = if self.answered.is_empty() {
+                (1..=self.responses.len()).map(|n| n.to_string()).collect()
+            } else {
+                self.answered.clone()
+            };
+            names.push("consensus".to_string());
+            return Err(CliError::ConfigError {
+                message: format!("No response '{}' in this run (expected one of: {})", name, names.join(", "))
+            });
+        };
+        let label = match self.answered.get(i) {
+            Some(tool) => format!("response {} ({})", i + 1, tool),
+            None => format!("response {}", i + 1),
+        };
+        Ok((label, Validator::strip_ansi_codes(&self.responses[i])))
+    }
+
+    /// The diff from the response or answer called `old` to the one called `new`; see [`RunReport::pick`].
+    pub fn diff(&self, old: &str, new: &str, by: Granularity, layout: Layout) -> Result<String> {
+        let (old_label, old_text) = self.pick(old)?;
+        let (new_label, new_text) = self.pick(new)?;
+        Ok(diff::render(&old_label, old_text.trim(), &new_label, new_text.trim(), by, layout))
+    }
+
+    pub fn render(&self, format: OutputFormat) -> String {
+        match format {
+            OutputFormat::Text => Validator::strip_ansi_codes(&self.consensus).trim().to_string(),
+            OutputFormat::Markdown => self.render_markdown(),
+            OutputFormat::Json => serde_json::to_string_pretty(self)
+                .unwrap_or_else(|e| format!("{{\"error\": \"{}\"}}", e)),
+        }
+    }
+
+    fn render_markdown(&self) -> String {
+        let mut md = format!("# {}\n\n## Consensus\n\n{}\n", self.prompt.trim(), Validator::strip_ansi_codes(&self.consensus).trim());
+        md.push_str(&format!(
+            "\n_Strategy: {}{}; solvers: {}_\n",
+            self.strategy,
+            self.consensus_tool.as_ref().map(|c| format!(" ({})", c)).unwrap_or_default(),
+            self.solvers.join(", ")
+        ));
+        if !self.judges.is_empty() {
+            md.push_str(&format!("\n_Judges: {}_\n", self.judges.join(", ")));
+        }
+        if !self.weights.is_empty() {
+            md.push_str(&format!("\n_Weights: {}_\n", self.weights.iter().map(|(tool, weight)| format!("{} {}", tool, weight)).collect::<Vec<_>>().join(", ")));
+        }
+        if !self.presentation.is_empty() {
+            md.push_str(&format!(
+                "\n_Order shown to the judge{}: {}_\n",
+                self.seed.map(|seed| format!(" (seed {})", seed)).unwrap_or_default(),
+                self.presentation.iter().map(|p| format!("{} (response {})", p.solver, p.response)).collect::<Vec<_>>().join(", ")
+            ));
+        }
+        if let Some(attribution) = self.attribution.as_ref().filter(|a| !a.invalid.is_empty()) {
+            md.push_str(&format!("\n_Citations of responses that do not exist, removed: {}_\n", attribution.invalid.join(", ")));
+        }
+        if !self.redacted.is_empty() {
+            md.push_str(&format!("\n_Masked before sending: {}_\n", self.redacted.join(", ")));
+        }
+        for substitution in &self.substitutions {
+            md.push_str(&format!("\n_{} answered for {} ({})_\n", substitution.replaced_by, substitution.solver, substitution.reason));
+        }
+
+        if !self.responses.is_empty() {
+            md.push_str("\n## Responses\n");
+            for (i, response) in self.responses.iter().enumerate() {
+                match self.answered.get(i) {
+                    Some(tool) => md.push_str(&format!("\n### Response {} ({})\n\n", i + 1, tool)),
+                    None => md.push_str(&format!("\n### Response {}\n\n", i + 1)),
+                }
+                if let Some(flag) = self.flagged.iter().find(|f| f.response == i + 1) {
+                    md.push_str(&format!(
+                        "> ⚠️ Possible prompt injection: {}{}\n\n",
+                        flag.matches.iter().map(|m| format!("\"{}\"", m)).collect::<Vec<_>>().join(", "),
+                        if flag.quarantined { " (left out of the consensus)" } else { "" }
+                    ));
+                }
+                md.push_str(&format!("{}\n", Validator::strip_ansi_codes(response).trim()));
+            }
+        }
+        md
+    }
+}
@@ -0,0 +1,333 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use crate::{Config, ToolConfig, ToolManager, SolverRun, SolverSlot, Redaction, Scheduler, ConsensusEngine, Validator, Router, ProfileConfig, RunReport, Verdict, CliError, Result, ui};
+use crate::{consensus::{Strategy, Task}, dryrun::{DryRun, Invocation}, redaction::{self, Guarded, RedactionPolicy}, privacy, injection, presentation::{self, Presentation}, attribution, weights, progress::{self, Event}};
+use log::{info, warn};
+
+/// How prompts are answered: the layered run settings plus the per-run flags.
+#[derive(Clone, Debug, Default)]
+pub struct RunOptions {
+    pub solvers: Vec<String>,
+    pub consensus: Vec<String>,
+    pub strategy: Strategy,
+    pub judges: usize,
+    pub reconcile: Strategy,
+    pub weights: HashMap<String, f64>,
+    pub policy: RedactionPolicy,
+    /// Pick solvers from each prompt's topic instead of `solvers`.
+    pub auto: bool,
+    /// Only use local tools, whatever the prompt contains.
+    pub local_only: bool,
+    pub quarantine: bool,
+    pub seed: Option<u64>,
+    pub strip_identity: bool,
+    pub attribute: bool,
+}
+
+impl RunOptions {
+    /// Options from `settings` and the configuration defaults, before any flags are applied.
+    pub fn new(settings: &ProfileConfig, config: &Config) -> Result<Self> {
+        let strategy = settings.strategy.unwrap_or_default();
+        Ok(RunOptions {
+            solvers: settings.solvers.clone(),
+            consensus: settings.consensus.clone(),
+            strategy,
+            judges: settings.judges.unwrap_or(1),
+            reconcile: settings.reconcile.unwrap_or_default(),
+            weights: weights::resolve(config, settings)?,
+            policy: config.redaction.policy,
+            auto: false,
+            local_only: false,
+            quarantine: config.injection.quarantine,
+            seed: None,
+            strip_identity: config.presentation.strip_identity,
+            // `attribute = true` in the config only applies to runs that have a judge
+            attribute: config.presentation.attribute && strategy == Strategy::Judge,
+        })
+    }
+
+    /// Problems that do not depend on the prompt, so a batch can report them once.
+    pub fn validate(&self, config: &Config) -> Result<()> {
+        let invalid = |message: String| Err(CliError::ConfigError { message });
+        if !self.auto && self.solvers.is_empty() {
+            return invalid("No solvers given - use -s, --auto or a profile with solvers".to_string());
+        }
+        if self.strategy == Strategy::Judge && self.consensus.is_empty() {
+            return invalid("No consensus tool given - use -c or a profile with a consensus tool".to_string());
+        }
+        if matches!(self.reconcile, Strategy::Concat | Strategy::First) {
+            return invalid("Judges can only be reconciled with judge or vote".to_string());
+        }
+        if self.attribute && self.strategy != Strategy::Judge {
+            return invalid(format!("--attribute needs the judge strategy, not {}", self.strategy));
+        }
+        let solvers: &[String] = if self.auto { &[] } else { &self.solvers };
+        Validator::validate_tools(solvers, &self.consensus, config)
+    }
+}
+
+/// A prompt made ready for the tools: what is sent, by which tools, under which configuration.
+struct Prepared<'a> {
+    redaction: Redaction,
+    sent: String,
+    slots: Vec<SolverSlot>,
+    consensus_tools: Vec<(String, ToolConfig)>,
+    config: Cow<'a, Config>,
+}
+
+/// What stands between a prompt and the tools, once its findings are in `redaction`: the
+/// prompt checks, `local_only` and the redaction `policy`. Returns the prompt to send and which
+/// of `solvers` and `consensus` may see it. Runs and `ai-co eval` cases both go through it.
+pub fn guard(prompt: &str, redaction: &Redaction, policy: RedactionPolicy, local_only: bool, solvers: &[String], consensus: &[String], config: &Config) -> Result<Guarded> {
+    Validator::sanitize_prompt(prompt)?;
+    let local_run = if local_only {
+        Some(privacy::restrict_to_local(solvers, consensus, config)?)
+    } else {
+        None
+    };
+    let (solvers, consensus) = match &local_run {
+        Some(run) => (run.solvers.as_slice(), run.consensus.as_slice()),
+        None => (solvers, consensus),
+    };
+    let guarded = redaction::guard(policy, prompt, redaction, solvers, consensus, config)?;
+    Ok(Guarded { restricted: local_run.or(guarded.restricted), ..guarded })
+}
+
+/// Redaction, solver selection and the tool availability checks, which start no tool except
+/// the router of `--auto`. A `dry_run` does not start the router and keeps the tools that are
+/// not available, for [`dry_run`] to point out.
+async fn prepare<'a>(prompt: &str, options: &RunOptions, config: &'a Config, dry_run: bool) -> Result<Prepared<'a>> {
+    // Secrets are detected before anything, including the router, sees the prompt
+    let policy = options.policy;
+    let redaction = Validator::redact(prompt, &config.redaction)?;
+    let local_only = options.local_only || (policy == RedactionPolicy::LocalOnly && !redaction.is_empty());
+
+    let solvers = if options.auto {
+        let routed_prompt = if policy == RedactionPolicy::Off { prompt } else { &redaction.text };
+        auto_select_solvers(config, routed_prompt, local_only, dry_run).await
+    } else {
+        options.solvers.clone()
+    };
+    if solvers.is_empty() {
+        return Err(CliError::ConfigError { message: "No solvers given - use -s, --auto or a profile with solvers".to_string() });
+    }
+    Validator::validate_tools(&solvers, &options.consensus, config)?;
+
+    // Only the judge strategy runs the consensus tool
+    let consensus = if options.strategy == Strategy::Judge { options.consensus.clone() } else { Vec::new() };
+    let guarded = guard(prompt, &redaction, policy, options.local_only, &solvers, &consensus, config)?;
+    if !redaction.is_empty() {
+        ui::show_redactions(policy, &redaction.kinds());
+    }
+    if let Some(run) = &guarded.restricted {
+        ui::show_local_only(run);
+    }
+
+    // Check tool availability
+    let config = if local_only {
+        let mut restricted = config.clone();
+        privacy::drop_cloud_fallbacks(&mut restricted);
+        Cow::Owned(restricted)
+    } else {
+        Cow::Borrowed(config)
+    };
+    let usable = |tool: &ToolConfig| dry_run || ToolManager::is_available(tool);
+    let (slots, unavailable_tools) = ToolManager::plan_slots_where(&guarded.solvers, &config, usable);
+
+    let (consensus_tools, unavailable_consensus): (Vec<_>, Vec<_>) = guarded.consensus.iter()
+        .map(|key| (key.clone(), config.tools[key].clone()))
+        .partition(|(_, tool)| usable(tool));
+    if consensus_tools.is_empty() && !unavailable_consensus.is_empty() {
+        return Err(CliError::ConsensusFailed { reason: format!("consensus tool '{}' not available", guarded.consensus.join(", ")) });
+    }
+    let unavailable_tools: Vec<String> = unavailable_tools.into_iter()
+        .chain(unavailable_consensus.into_iter().map(|(key, _)| key))
+        .collect();
+
+    if !unavailable_tools.is_empty() {
+        warn!("Unavailable tools: {}", unavailable_tools.join(", "));
+        ui::show_unavailable(&unavailable_tools);
+    }
+
+    if slots.is_empty() {
+        return Err(CliError::AllSolversFailed);
+    }
+    Ok(Prepared { redaction, sent: guarded.prompt, slots, consensus_tools, config })
+}
+
+/// Answers one prompt: redaction, solver selection, the solvers, injection screening and the
+/// consensus, reported as a [`RunReport`]. Tools take their slots from `scheduler`.
+pub async fn answer(prompt: &str, options: &RunOptions, config: &Config, scheduler: &Scheduler) -> Result<RunReport> {
+    let Prepared { redaction, sent, slots, consensus_tools, config } = prepare(prompt, options, config, false).await?;
+    let (policy, strategy) = (options.policy, options.strategy);
+
+    // Run solvers
+    let SolverRun { responses, answered, timings, substitutions } = ToolManager::run_solvers(&slots, &sent, scheduler).await?;
+
+    // Responses are untrusted input to the judge
+    let screened = injection::screen(&responses, &config.injection, options.quarantine)?;
+    ui::show_flagged(&screened.flagged);
+
+    // Hide position and identity from the judge
+    let presentation = if strategy == Strategy::Judge {
+        let entries: Vec<(usize, String, String)> = screened.positions.iter()
+            .zip(&screened.kept)
+            .map(|(position, text)| (*position, answered[position - 1].clone(), text.clone()))
+            .collect();
+        presentation::present(&entries, &config.presentation, options.seed, options.strip_identity)?
+    } else {
+        Presentation::default()
+    };
+    ui::show_presentation(&presentation);
+
+    // Trust in each solver, for the vote and the judge
+    let weighted = weights::effective(&options.weights, &screened.positions.iter().map(|position| answered[position - 1].clone()).collect::<Vec<_>>());
+    ui::show_weights(&weighted);
+    let weight_of = |tool: &String| weighted.get(tool).copied().unwrap_or(1.0);
+
+    // Get consensus
+    ui::show_consensus_start();
+    progress::emit(Event::ConsensusStarted { strategy: strategy.to_string() });
+
+    let consensus_start = std::time::Instant::now();
+    let (verdict, strategy) = if strategy == Strategy::Judge {
+        let weights: Vec<f64> = presentation.mapping.iter().map(|p| weight_of(&p.solver)).collect();
+        match ConsensusEngine::judge(&consensus_tools, options.judges, options.reconcile, &presentation.responses, &weights, options.attribute, scheduler).await {
+            Ok(verdict) => (verdict, strategy),
+            // Every consensus tool failed; the solvers' answers are still worth showing
+            Err(e) => {
+                warn!("{}", e);
+                ui::show_consensus_failed(&e);
+                let text = ConsensusEngine::aggregate(Strategy::Concat, None, screened.kept, &[], &sent).await?;
+                (Verdict { text, ..Default::default() }, Strategy::Concat)
+            },
+        }
+    } else {
+        let votes: Vec<f64> = screened.positions.iter().map(|position| weight_of(&answered[position - 1])).collect();
+        let text = ConsensusEngine::aggregate(strategy, None, screened.kept, &votes, &sent).await?;
+        (Verdict { text, ..Default::default() }, strategy)
+    };
+    let consensus_time = consensus_start.elapsed();
+
+    ui::show_consensus_complete(consensus_time.as_secs_f32());
+    ui::show_verdict(&verdict);
+
+    let mut consensus = redaction.restore(&verdict.text);
+    // A judge that fell back to concatenation wrote no citations
+    let attribution = (options.attribute && strategy == Strategy::Judge).then(|| attribution::attribute(&consensus, &presentation.mapping));
+    if let Some(attribution) = &attribution {
+        ui::show_attribution(attribution);
+        consensus = attribution.text.clone();
+    }
+
+    progress::emit(Event::Consensus { tool: verdict.tool.clone(), text: consensus.clone() });
+    info!("Answered with {} response(s) by {}", responses.len(), strategy);
+    Ok(RunReport {
+        prompt: prompt.to_string(),
+        solvers: slots.iter().map(|slot| slot.solver.clone()).collect(),
+        consensus_tool: verdict.tool.clone(),
+        judges: verdict.judges.clone(),
+        strategy: strategy.to_string(),
+        consensus,
+        responses: responses.iter().map(|r| redaction.restore(r)).collect(),
+        answered,
+        redacted: if policy == RedactionPolicy::Mask { redaction.kinds() } else { Vec::new() },
+        flagged: screened.flagged,
+        presentation: presentation.mapping,
+        seed: presentation.seed,
+        attribution,
+        weights: weighted,
+        timings,
+        substitutions,
+    })
+}
+
+/// Describes the processes [`answer`] would start for `prompt`, with placeholders for the
+/// responses the consensus tools would see, without starting any. With `--auto`, solvers are
+/// picked by the keyword rules, not the router tool.
+pub async fn dry_run(prompt: &str, options: &RunOptions, config: &Config) -> Result<DryRun> {
+    let Prepared { sent, slots, consensus_tools, config, .. } = prepare(prompt, options, config, true).await?;
+    let mut plan = DryRun { strategy: options.strategy.to_string(), ..Default::default() };
+    if options.auto && config.routing.router.is_some() {
+        plan.notes.push("A real run asks the router tool to pick the solvers; these are the keyword rules' picks".to_string());
+    }
+
+    // Shown all the same, since the plan matters before the tools are installed
+    let mut missing: Vec<&str> = Vec::new();
+    for (key, tool) in slots.iter().flat_map(|slot| &slot.chain).chain(&consensus_tools) {
+        if !ToolManager::is_available(tool) && !missing.contains(&key.as_str()) {
+            missing.push(key);
+        }
+    }
+    if !missing.is_empty() {
+        plan.notes.push(format!("Not available here, so a real run would skip: {}", missing.join(", ")));
+    }
+
+    for slot in &slots {
+        for (i, (key, tool)) in slot.chain.iter().enumerate() {
+            let stage = if i == 0 { "solver".to_string() } else { format!("fallback for {}", slot.solver) };
+            plan.invocations.push(Invocation::new(&stage, key, tool, &sent)?);
+        }
+    }
+    if options.strategy != Strategy::Judge {
+        plan.notes.push(format!("The responses are combined by {} without a consensus tool", options.strategy));
+        return Ok(plan);
+    }
+
+    // Every solver is assumed to answer; a real run leaves failed and quarantined ones out
+    let entries: Vec<(usize, String, String)> = slots.iter().enumerate()
+        .map(|(i, slot)| (i + 1, slot.solver.clone(), format!("(response of {})", slot.solver)))
+        .collect();
+    let presentation = presentation::present(&entries, &config.presentation, options.seed, false)?;
+    if let Some(seed) = presentation.seed {
+        plan.notes.push(format!("The judge sees the responses in the order of seed {} (pass --seed {} to keep it)", seed, seed));
+    }
+    let solvers: Vec<String> = slots.iter().map(|slot| slot.solver.clone()).collect();
+    let weighted = weights::effective(&options.weights, &solvers);
+    let weights: Vec<f64> = presentation.mapping.iter().map(|p| weighted.get(&p.solver).copied().unwrap_or(1.0)).collect();
+    let guidance = ConsensusEngine::weighting(&weights);
+    let task = if options.attribute { Task::Cite } else { Task::Synthesize };
+    let nonce = ConsensusEngine::delimiter_nonce();
+    let brief = ConsensusEngine::brief(task, &presentation.responses, &nonce, &guidance);
+
+    let judges = options.judges.max(1).min(consensus_tools.len());
+    for (i, (key, tool)) in consensus_tools.iter().take(judges).enumerate() {
+        let stage = if judges == 1 { "judge".to_string() } else { format!("judge {} of {}", i + 1, judges) };
+        plan.invocations.push(Invocation::new(&stage, key, tool, &brief)?);
+    }
+    let spare: Vec<&str> = consensus_tools.iter().skip(judges).map(|(key, _)| key.as_str()).collect();
+    if !spare.is_empty() {
+        plan.notes.push(format!("A failed judge is replaced by {}, in that order", spare.join(", ")));
+    }
+    if judges > 1 {
+        if options.reconcile == Strategy::Judge {
+            let syntheses: Vec<String> = consensus_tools.iter().take(judges).map(|(key, _)| format!("(synthesis of {})", key)).collect();
+            let (key, tool) = &consensus_tools[0];
+            plan.invocations.push(Invocation::new("reconcile", key, tool, &ConsensusEngine::brief(Task::Reconcile, &syntheses, &nonce, ""))?);
+        } else {
+            plan.notes.push("The judges' syntheses are reconciled by vote".to_string());
+        }
+    }
+    Ok(plan)
+}
+
+/// Picks solvers for `prompt` among the configured tools. A `dry_run` picks by the keyword
+/// rules among every tool, installed or not, so the plan can be checked before installing them.
+async fn auto_select_solvers(config: &Config, prompt: &str, local_only: bool, dry_run: bool) -> Vec<String> {
+    let mut candidates: Vec<_> = config.tools.iter()
+        .filter(|(_, tool)| dry_run || ToolManager::is_available(tool))
+        .filter(|(_, tool)| !local_only || tool.is_local())
+        .map(|(key, tool)| (key.clone(), tool.clone()))
+        .collect();
+    candidates.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let router = config.routing.router.as_ref()
+        .filter(|_| !dry_run)
+        .map(|key| &config.tools[key])
+        .filter(|tool| ToolManager::is_available(tool))
+        .filter(|tool| !local_only || tool.is_local());
+
+    let decision = Router::route(prompt, &candidates, &config.routing, router).await;
+    ui::show_routing(&decision.solvers, &decision.topics, &decision.reasons);
+    decision.solvers
+}
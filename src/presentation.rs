@@ -0,0 +1,158 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::{CliError, Result, constants::{IDENTITY_MODELS, IDENTITY_VENDORS}};
+
+lazy_static! {
+    /// Phrases in which a model names itself, e.g. "As Claude, " or "I am Gemini, a model by Google."
+    static ref IDENTITY_REGEXES: Vec<Regex> = {
+        let models = IDENTITY_MODELS.join("|");
+        let vendors = IDENTITY_VENDORS.join("|");
+        let made_by = format!(r"(?:developed|created|made|trained|built) by (?:{})", vendors);
+        vec![
+            Regex::new(&format!(r"(?i)\bas (?:an? )?(?:{}|ai|ai (?:assistant|model)|(?:large )?language model)(?:,? (?:an? )?[\w -]{{0,40}}?{})?,\s*", models, made_by)).unwrap(),
+            Regex::new(&format!(r"(?i)\bI(?:'m| am) (?:{})\b(?:,? (?:an? )?[\w -]{{0,40}}?{})?[.!]?\s*", models, made_by)).unwrap(),
+        ]
+    };
+}
+
+/// The `[presentation]` section: how responses are shown to the judge.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PresentationConfig {
+    /// Show the responses in random order, so no solver benefits from its position.
+    pub shuffle: bool,
+    /// Remove phrases in which a model names itself or its vendor.
+    pub strip_identity: bool,
+    /// Extra case-insensitive regexes for self-identifying phrases.
+    pub identity_patterns: Vec<String>,
+    /// Have the judge cite the responses behind each statement; see [`crate::attribution`].
+    pub attribute: bool,
+}
+
+impl Default for PresentationConfig {
+    fn default() -> Self {
+        PresentationConfig { shuffle: true, strip_identity: false, identity_patterns: Vec::new(), attribute: false }
+    }
+}
+
+impl PresentationConfig {
+    pub fn validate(&self) -> Result<()> {
+        self.identity_regexes().map(|_| ())
+    }
+
+    fn identity_regexes(&self) -> Result<Vec<Regex>> {
+        self.identity_patterns.iter()
+            .map(|pattern| Regex::new(&format!("(?i){}", pattern)).map_err(|e| CliError::ConfigError {
+                message: format!("Identity pattern '{}' is not a valid regex: {}", pattern, e)
+            }))
+            .collect()
+    }
+}
+
+/// Which response the judge saw under which label.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Presented {
+    /// The judge saw this response as "Response <label>".
+    pub label: usize,
+    /// 1-based position in the report's `responses`.
+    pub response: usize,
+    /// The tool that wrote the response.
+    pub solver: String,
+}
+
+/// Responses in the order and form the judge sees them.
+#[derive(Debug, Clone, Default)]
+pub struct Presentation {
+    pub responses: Vec<String>,
+    pub mapping: Vec<Presented>,
+    /// Seed of the shuffle; `None` when the order was kept.
+    pub seed: Option<u64>,
+    /// Number of self-identifying phrases removed.
+    pub stripped: usize,
+}
+
+/// Prepares `responses` for the judge. Each entry is (1-based position in the report, solver,
+/// text). Shuffles them with `seed` (random when `None`) if `config.shuffle` is set or a seed
+/// is given, and strips self-identifying phrases if `strip_identity` is set.
+pub fn present(responses: &[(usize, String, String)], config: &PresentationConfig, seed: Option<u64>, strip_identity: bool) -> Result<Presentation> {
+    let extra = config.identity_regexes()?;
+    let mut order: Vec<usize> = (0..responses.len()).collect();
+    let mut presentation = Presentation::default();
+    if config.shuffle || seed.is_some() {
+        let seed = seed.unwrap_or_else(random_seed);
+        shuffle(&mut order, seed);
+        presentation.seed = Some(seed);
+    }
+
+    for (label, index) in order.into_iter().enumerate() {
+        let (response, solver, text) = &responses[index];
+        let text = if strip_identity {
+            let (text, count) = strip(text, &extra);
+            presentation.stripped += count;
+            text
+        } else {
+            text.clone()
+        };
+        presentation.responses.push(text);
+        presentation.mapping.push(Presented { label: label + 1, response: *response, solver: solver.clone() });
+    }
+    Ok(presentation)
+}
+
+/// Removes self-identifying phrases, restoring the capital letter of a sentence they started.
+/// Built-in phrases only count at the start of a sentence, so "tools such as Gemini, ..." is
+/// kept; `extra` patterns match anywhere. Returns the text and the number of phrases removed.
+pub fn strip(text: &str, extra: &[Regex]) -> (String, usize) {
+    let mut text = text.to_string();
+    let mut count = 0;
+    let regexes = IDENTITY_REGEXES.iter().map(|r| (r, true)).chain(extra.iter().map(|r| (r, false)));
+    for (regex, sentence_start) in regexes {
+        let mut out = String::with_capacity(text.len());
+        let mut last = 0;
+        for m in regex.find_iter(&text) {
+            let before = text[..m.start()].trim_end_matches([' ', '\t']);
+            let starts_sentence = before.is_empty() || before.ends_with(['.', '!', '?', '\n', ':']);
+            if sentence_start && !starts_sentence {
+                continue;
+            }
+            out.push_str(&text[last..m.start()]);
+            last = m.end();
+            count += 1;
+            if starts_sentence {
+                if let Some(first) = text[last..].chars().next() {
+                    out.extend(first.to_uppercase());
+                    last += first.len_utf8();
+                }
+            }
+        }
+        out.push_str(&text[last..]);
+        text = out;
+    }
+    (text, count)
+}
+
+/// Fisher-Yates shuffle driven by SplitMix64, so a seed gives the same order everywhere.
+fn shuffle(order: &mut [usize], seed: u64) {
+    let mut state = seed;
+    let mut next = || {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    };
+    for i in (1..order.len()).rev() {
+        let j = (next() % (i as u64 + 1)) as usize;
+        order.swap(i, j);
+    }
+}
+
+fn random_seed() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or_default());
+    hasher.finish()
+}
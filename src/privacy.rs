@@ -0,0 +1,83 @@
+use serde::Deserialize;
+use crate::{Config, CliError, Result, ToolManager};
+
+/// Where a tool sends the prompt.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Privacy {
+    /// The prompt goes to a vendor's servers.
+    #[default]
+    Cloud,
+    /// The prompt never leaves this machine.
+    Local,
+}
+
+impl std::fmt::Display for Privacy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Privacy::Cloud => "cloud",
+            Privacy::Local => "local",
+        })
+    }
+}
+
+/// Solvers and consensus tools of a run restricted to local tools.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LocalRun {
+    pub solvers: Vec<String>,
+    pub consensus: Vec<String>,
+    /// Cloud solvers that were removed.
+    pub dropped: Vec<String>,
+    /// Cloud consensus tools that were removed or replaced.
+    pub replaced_consensus: Vec<String>,
+}
+
+fn is_local(tool: &str, config: &Config) -> bool {
+    config.tools.get(tool).map(|t| t.is_local()).unwrap_or(false)
+}
+
+/// Drops cloud tools from the solvers and the consensus role. When every consensus tool is
+/// cloud, the first local solver, or else the first available local tool, takes over. Fails
+/// closed when no local solver is left or no local tool can act as consensus.
+pub fn restrict_to_local(solvers: &[String], consensus: &[String], config: &Config) -> Result<LocalRun> {
+    let (local, dropped): (Vec<String>, Vec<String>) = solvers.iter().cloned().partition(|t| is_local(t, config));
+    if local.is_empty() {
+        return Err(CliError::LocalOnly {
+            reason: format!("none of the solvers ({}) is a local tool", solvers.join(", "))
+        });
+    }
+
+    let (local_consensus, cloud_consensus): (Vec<String>, Vec<String>) = consensus.iter().cloned().partition(|t| is_local(t, config));
+    let mut run = LocalRun { solvers: local, dropped, consensus: local_consensus, replaced_consensus: cloud_consensus };
+    if run.consensus.is_empty() && !run.replaced_consensus.is_empty() {
+        let mut fallbacks: Vec<&String> = config.tools.keys()
+            .filter(|key| is_local(key, config) && ToolManager::is_available(&config.tools[*key]))
+            .collect();
+        fallbacks.sort();
+        let replacement = run.solvers.first().or(fallbacks.first().copied()).ok_or_else(|| CliError::LocalOnly {
+            reason: format!("{} {} cloud and no local tool can provide consensus", run.replaced_consensus.join(", "), if run.replaced_consensus.len() == 1 { "is" } else { "are" })
+        })?;
+        run.consensus.push(replacement.clone());
+    }
+    Ok(run)
+}
+
+/// Removes cloud tools from every `fallback` chain, so a local-only run cannot fail over to them.
+pub fn drop_cloud_fallbacks(config: &mut Config) {
+    let local: Vec<String> = config.tools.iter().filter(|(_, t)| t.is_local()).map(|(key, _)| key.clone()).collect();
+    for tool in config.tools.values_mut() {
+        tool.fallback.retain(|fallback| local.contains(fallback));
+    }
+}
+
+/// Fails unless every tool is local; used where tools cannot be swapped, such as workflow stages.
+pub fn require_local(tools: &[String], config: &Config) -> Result<()> {
+    let cloud: Vec<&String> = tools.iter().filter(|t| !is_local(t, config)).collect();
+    if cloud.is_empty() {
+        Ok(())
+    } else {
+        Err(CliError::LocalOnly {
+            reason: format!("{} {} cloud", cloud.iter().map(|t| t.as_str()).collect::<Vec<_>>().join(", "), if cloud.len() == 1 { "is" } else { "are" })
+        })
+    }
+}
@@ -0,0 +1,104 @@
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use crate::{ToolConfig, CliError, Result, consensus::Strategy, output::OutputFormat};
+
+/// A named combination of solvers, consensus tool and run options (`[profiles.<name>]`).
+///
+/// Every field is optional so that profiles and command-line flags can be layered:
+/// values set on the command line override the profile, which overrides the defaults.
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ProfileConfig {
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub solvers: Vec<String>,
+    /// Consensus tools, tried in order when one fails; the first `judges` of them form a panel.
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub consensus: Vec<String>,
+    /// Number of consensus tools that each synthesize the responses (default 1).
+    pub judges: Option<usize>,
+    /// How a panel's syntheses are reconciled: `judge` (a final pass) or `vote`.
+    pub reconcile: Option<Strategy>,
+    /// Tool weights for this profile, overriding `weight` in the tool's own section.
+    #[serde(default)]
+    pub weights: HashMap<String, f64>,
+    /// Scores file written by `ai-co eval`; each tool's weight is scaled by its accuracy.
+    pub weights_from: Option<String>,
+    pub strategy: Option<Strategy>,
+    pub timeout_secs: Option<u64>,
+    pub format: Option<OutputFormat>,
+}
+
+/// Accepts `consensus = "claude"`, `"claude,gemini"` or `["claude", "gemini"]`.
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(list) => split_list(&list),
+        OneOrMany::Many(tools) => tools,
+    })
+}
+
+/// Splits a comma-separated list of tool keys.
+pub fn split_list(list: &str) -> Vec<String> {
+    list.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect()
+}
+
+impl ProfileConfig {
+    /// Returns a copy of `self` with every value set in `overrides` taking precedence.
+    pub fn overridden_by(&self, overrides: &ProfileConfig) -> ProfileConfig {
+        ProfileConfig {
+            description: if overrides.description.is_empty() { self.description.clone() } else { overrides.description.clone() },
+            solvers: if overrides.solvers.is_empty() { self.solvers.clone() } else { overrides.solvers.clone() },
+            consensus: if overrides.consensus.is_empty() { self.consensus.clone() } else { overrides.consensus.clone() },
+            judges: overrides.judges.or(self.judges),
+            reconcile: overrides.reconcile.or(self.reconcile),
+            weights: self.weights.iter().chain(&overrides.weights).map(|(tool, weight)| (tool.clone(), *weight)).collect(),
+            weights_from: overrides.weights_from.clone().or_else(|| self.weights_from.clone()),
+            strategy: overrides.strategy.or(self.strategy),
+            timeout_secs: overrides.timeout_secs.or(self.timeout_secs),
+            format: overrides.format.or(self.format),
+        }
+    }
+
+    pub fn validate(&self, name: &str, tools: &HashMap<String, ToolConfig>) -> Result<()> {
+        for tool in self.solvers.iter().chain(self.consensus.iter()).chain(self.weights.keys()) {
+            if !tools.contains_key(tool) {
+                return Err(CliError::ConfigError {
+                    message: format!("Profile '{}' references unknown tool '{}'", name, tool)
+                });
+            }
+        }
+        if self.judges == Some(0) {
+            return Err(CliError::ConfigError {
+                message: format!("Profile '{}' has judges = 0; use at least 1", name)
+            });
+        }
+        if matches!(self.reconcile, Some(Strategy::Concat | Strategy::First)) {
+            return Err(CliError::ConfigError {
+                message: format!("Profile '{}' can only reconcile judges with judge or vote", name)
+            });
+        }
+        if self.weights.values().any(|weight| !weight.is_finite()) {
+            return Err(CliError::ConfigError {
+                message: format!("Profile '{}' weights must be finite numbers", name)
+            });
+        }
+        if self.weights.values().any(|weight| *weight < 0.0) {
+            return Err(CliError::ConfigError {
+                message: format!("Profile '{}' has a negative weight", name)
+            });
+        }
+        if self.timeout_secs == Some(0) {
+            return Err(CliError::ConfigError {
+                message: format!("Profile '{}' has a zero timeout", name)
+            });
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+//! Progress events of a run, for live displays such as the `--tui` dashboard.
+//!
+//! Nothing is sent until a sink is installed with [`set_sink`]. Tools started by
+//! [`ToolManager::attempt`](crate::ToolManager) report their output as it arrives and can be
+//! cancelled one attempt at a time with [`cancel`].
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, atomic::{AtomicU64, Ordering}};
+use tokio::sync::{mpsc::UnboundedSender, Notify};
+
+/// Identifies one attempt of a tool to answer for a solver, unique within the process, so
+/// the same tool in two slots (or twice in one) is told apart.
+pub type AttemptId = u64;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// `tool` waits for a scheduler slot to answer for `solver` (itself or a fallback).
+    Queued { id: AttemptId, solver: String, tool: String },
+    Started { id: AttemptId },
+    /// Output printed by the attempt since the last event.
+    Output { id: AttemptId, text: String },
+    /// The attempt finished with its response or the reason it failed.
+    Finished { id: AttemptId, result: std::result::Result<String, String> },
+    /// The solvers are done and the responses are being combined by `strategy`.
+    ConsensusStarted { strategy: String },
+    /// The final answer, and the consensus tool that wrote it, if any.
+    Consensus { tool: Option<String>, text: String },
+}
+
+lazy_static::lazy_static! {
+    static ref SINK: Mutex<Option<UnboundedSender<Event>>> = Mutex::new(None);
+    static ref CANCELLATIONS: Mutex<HashMap<AttemptId, Arc<Notify>>> = Mutex::new(HashMap::new());
+}
+
+static NEXT_ATTEMPT: AtomicU64 = AtomicU64::new(1);
+
+tokio::task_local! {
+    /// The attempt the current task runs, so its output can be reported.
+    static CURRENT_ATTEMPT: AttemptId;
+}
+
+/// Sends events to `sink` from now on, or stops sending them.
+pub fn set_sink(sink: Option<UnboundedSender<Event>>) {
+    if let Ok(mut current) = SINK.lock() {
+        *current = sink;
+    }
+}
+
+pub fn emit(event: Event) {
+    if let Ok(sink) = SINK.lock() {
+        if let Some(sink) = sink.as_ref() {
+            let _ = sink.send(event);
+        }
+    }
+}
+
+/// Whether anyone listens, so callers can skip building events nobody reads.
+pub fn enabled() -> bool {
+    SINK.lock().map(|sink| sink.is_some()).unwrap_or(false)
+}
+
+/// Runs `future` as attempt `id`, so that its output is reported and it can be cancelled.
+pub async fn scope<F: Future>(id: AttemptId, future: F) -> F::Output {
+    CURRENT_ATTEMPT.scope(id, future).await
+}
+
+/// The attempt the current task runs, if any.
+pub fn current_attempt() -> Option<AttemptId> {
+    CURRENT_ATTEMPT.try_with(|id| *id).ok()
+}
+
+/// Reports output of the current attempt.
+pub fn output(text: &str) {
+    if let Some(id) = current_attempt().filter(|_| enabled()) {
+        emit(Event::Output { id, text: text.to_string() });
+    }
+}
+
+/// An attempt that can be cancelled until this is dropped.
+pub struct Registration(AttemptId);
+
+impl Registration {
+    pub fn id(&self) -> AttemptId {
+        self.0
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        CANCELLATIONS.lock().unwrap_or_else(|e| e.into_inner()).remove(&self.0);
+    }
+}
+
+/// Registers a new attempt for [`cancel`].
+pub fn register() -> Registration {
+    let id = NEXT_ATTEMPT.fetch_add(1, Ordering::Relaxed);
+    CANCELLATIONS.lock().unwrap_or_else(|e| e.into_inner()).insert(id, Arc::default());
+    Registration(id)
+}
+
+/// Asks attempt `id` to stop, queued or running; an attempt that has ended is left alone.
+pub fn cancel(id: AttemptId) {
+    if let Some(signal) = CANCELLATIONS.lock().unwrap_or_else(|e| e.into_inner()).get(&id) {
+        signal.notify_one();
+    }
+}
+
+/// Resolves when attempt `id` is cancelled; never for an attempt that is not registered.
+pub async fn cancelled(id: AttemptId) {
+    let signal = CANCELLATIONS.lock().unwrap_or_else(|e| e.into_inner()).get(&id).cloned();
+    match signal {
+        Some(signal) => signal.notified().await,
+        None => std::future::pending().await,
+    }
+}
@@ -0,0 +1,154 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use crate::{Config, CliError, Result, privacy::{self, LocalRun}};
+
+/// Names of the built-in detectors, in the order they claim overlapping matches.
+pub const BUILTIN_DETECTORS: &[&str] = &[
+    "private_key", "aws_access_key", "aws_secret_key", "jwt", "api_key", "secret", "email", "ip",
+];
+
+/// What happens when a prompt contains secrets or personal data.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RedactionPolicy {
+    /// Send the prompt unchanged.
+    Off,
+    /// Replace each finding with a placeholder and restore it in the output.
+    #[default]
+    Mask,
+    /// Refuse to run.
+    Block,
+    /// Send the prompt unchanged, but only to local tools (see `privacy::restrict_to_local`).
+    LocalOnly,
+}
+
+impl std::str::FromStr for RedactionPolicy {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "off" => Ok(RedactionPolicy::Off),
+            "mask" => Ok(RedactionPolicy::Mask),
+            "block" => Ok(RedactionPolicy::Block),
+            "local-only" | "local" => Ok(RedactionPolicy::LocalOnly),
+            _ => Err(CliError::ConfigError {
+                message: format!("Unknown redaction policy '{}' (expected mask, block, local-only or off)", s)
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for RedactionPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            RedactionPolicy::Off => "off",
+            RedactionPolicy::Mask => "mask",
+            RedactionPolicy::Block => "block",
+            RedactionPolicy::LocalOnly => "local-only",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The `[redaction]` section.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct RedactionConfig {
+    #[serde(default)]
+    pub policy: RedactionPolicy,
+    /// Extra detectors: name to regular expression. The whole match is redacted.
+    #[serde(default)]
+    pub patterns: HashMap<String, String>,
+    /// Built-in detectors to turn off (see `BUILTIN_DETECTORS`).
+    #[serde(default)]
+    pub disabled: Vec<String>,
+}
+
+impl RedactionConfig {
+    pub fn validate(&self) -> Result<()> {
+        for (name, pattern) in &self.patterns {
+            regex::Regex::new(pattern).map_err(|e| CliError::ConfigError {
+                message: format!("Redaction pattern '{}' is not a valid regex: {}", name, e)
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// One secret or piece of personal data found in a prompt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub kind: String,
+    pub value: String,
+    pub placeholder: String,
+}
+
+/// A prompt with its findings replaced by placeholders such as `[REDACTED_EMAIL_1]`.
+#[derive(Debug, Clone, Default)]
+pub struct Redaction {
+    pub text: String,
+    pub findings: Vec<Finding>,
+}
+
+impl Redaction {
+    pub fn is_empty(&self) -> bool {
+        self.findings.is_empty()
+    }
+
+    /// Distinct kinds of data found, sorted.
+    pub fn kinds(&self) -> Vec<String> {
+        let mut kinds: Vec<String> = self.findings.iter().map(|f| f.kind.clone()).collect();
+        kinds.sort();
+        kinds.dedup();
+        kinds
+    }
+
+    /// Puts the original values back wherever a tool echoed a placeholder unchanged.
+    pub fn restore(&self, text: &str) -> String {
+        self.findings.iter().fold(text.to_string(), |text, finding| text.replace(&finding.placeholder, &finding.value))
+    }
+}
+
+/// The prompt and tools a run may use after applying the redaction policy.
+#[derive(Debug, Clone)]
+pub struct Guarded {
+    pub prompt: String,
+    pub solvers: Vec<String>,
+    pub consensus: Vec<String>,
+    /// Set when the `local-only` policy restricted the run to local tools.
+    pub restricted: Option<LocalRun>,
+}
+
+/// Applies `policy` to a prompt whose findings are in `redaction`.
+pub fn guard(policy: RedactionPolicy, original: &str, redaction: &Redaction, solvers: &[String], consensus: &[String], config: &Config) -> Result<Guarded> {
+    let unchanged = || Guarded {
+        prompt: original.to_string(),
+        solvers: solvers.to_vec(),
+        consensus: consensus.to_vec(),
+        restricted: None,
+    };
+    if redaction.is_empty() {
+        return Ok(unchanged());
+    }
+
+    let kinds = redaction.kinds().join(", ");
+    match policy {
+        RedactionPolicy::Off => Ok(unchanged()),
+        RedactionPolicy::Mask => Ok(Guarded { prompt: redaction.text.clone(), ..unchanged() }),
+        RedactionPolicy::Block => Err(CliError::SensitiveData {
+            kinds,
+            reason: "the redaction policy is 'block'".to_string(),
+        }),
+        RedactionPolicy::LocalOnly => {
+            let run = privacy::restrict_to_local(solvers, consensus, config).map_err(|e| match e {
+                CliError::LocalOnly { reason } => CliError::SensitiveData { kinds, reason },
+                other => other,
+            })?;
+            Ok(Guarded {
+                prompt: original.to_string(),
+                solvers: run.solvers.clone(),
+                consensus: run.consensus.clone(),
+                restricted: Some(run),
+            })
+        },
+    }
+}
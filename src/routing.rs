@@ -0,0 +1,194 @@
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use crate::{ToolConfig, ToolManager, Validator, Result};
+use log::{info, warn};
+
+/// Tag assumed for prompts that match no rule.
+pub const GENERAL_TAG: &str = "general";
+
+const DEFAULT_RULES: &[(&str, &[&str])] = &[
+    ("cloud", &["aws", "azure", "gcp", "cloud", "kubernetes", "k8s", "terraform", "docker", "lambda", "s3", "ec2", "iam", "serverless", "infrastructure", "devops"]),
+    ("code", &["code", "function", "bug", "compile", "compiler", "rust", "python", "javascript", "typescript", "java", "golang", "regex", "refactor", "api", "sql", "debug", "stacktrace", "algorithm"]),
+    ("privacy", &["password", "secret", "confidential", "private", "internal", "patient", "medical", "salary", "ssn", "credentials", "sensitive"]),
+    ("multilingual", &["translate", "translation", "french", "spanish", "german", "italian", "japanese", "chinese", "portuguese", "language"]),
+    ("writing", &["write", "essay", "email", "blog", "summarize", "summary", "rewrite", "proofread", "letter", "article"]),
+    ("reasoning", &["why", "prove", "explain", "compare", "tradeoff", "tradeoffs", "analyze", "reason", "math", "logic"]),
+];
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct RoutingConfig {
+    /// Maximum number of solvers picked by `--auto`.
+    #[serde(default = "default_max_solvers")]
+    pub max_solvers: usize,
+    /// Optional cheap tool asked to classify the prompt instead of the keyword rules.
+    pub router: Option<String>,
+    /// Extra or overriding keyword rules, keyed by tag.
+    #[serde(default)]
+    pub rules: HashMap<String, Vec<String>>,
+}
+
+fn default_max_solvers() -> usize {
+    3
+}
+
+impl Default for RoutingConfig {
+    fn default() -> Self {
+        Self {
+            max_solvers: default_max_solvers(),
+            router: None,
+            rules: HashMap::new(),
+        }
+    }
+}
+
+impl RoutingConfig {
+    /// Built-in rules merged with the configured ones; configured keywords replace built-in ones per tag.
+    pub fn rules(&self) -> BTreeMap<String, Vec<String>> {
+        let mut rules: BTreeMap<String, Vec<String>> = DEFAULT_RULES.iter()
+            .map(|(tag, words)| (tag.to_string(), words.iter().map(|w| w.to_string()).collect()))
+            .collect();
+        for (tag, words) in &self.rules {
+            rules.insert(tag.to_lowercase(), words.iter().map(|w| w.to_lowercase()).collect());
+        }
+        rules
+    }
+}
+
+/// The solvers chosen for a prompt and a human-readable reason for each.
+#[derive(Debug, Clone, Default)]
+pub struct RoutingDecision {
+    pub topics: Vec<String>,
+    pub solvers: Vec<String>,
+    pub reasons: Vec<String>,
+}
+
+pub struct Router;
+
+impl Router {
+    /// Returns the tags whose keywords appear in the prompt, with the matching keywords.
+    pub fn classify(prompt: &str, rules: &BTreeMap<String, Vec<String>>) -> BTreeMap<String, Vec<String>> {
+        let lower = prompt.to_lowercase();
+        let words: Vec<&str> = lower
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .collect();
+
+        let mut topics = BTreeMap::new();
+        for (tag, keywords) in rules {
+            let hits: Vec<String> = keywords.iter()
+                .filter(|k| if k.contains(' ') { lower.contains(k.as_str()) } else { words.contains(&k.as_str()) })
+                .cloned()
+                .collect();
+            if !hits.is_empty() {
+                topics.insert(tag.clone(), hits);
+            }
+        }
+        topics
+    }
+
+    /// Ranks candidate tools by how many of the prompt's topics their tags cover, making sure
+    /// every detected topic gets at least one tool when `max_solvers` allows it.
+    pub fn select(topics: &BTreeMap<String, Vec<String>>, candidates: &[(String, ToolConfig)], max_solvers: usize) -> RoutingDecision {
+        let mut scored: Vec<(usize, &String, Vec<&String>)> = candidates.iter()
+            .map(|(key, tool)| {
+                let matched: Vec<&String> = topics.keys()
+                    .filter(|topic| tool.tags.iter().any(|t| t.eq_ignore_ascii_case(topic)))
+                    .collect();
+                let score = matched.iter().map(|t| topics[*t].len()).sum();
+                (score, key, matched)
+            })
+            .filter(|(score, _, _)| *score > 0)
+            .collect();
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+
+        let mut decision = RoutingDecision {
+            topics: topics.keys().cloned().collect(),
+            ..Default::default()
+        };
+
+        // Cover each topic with its best tool first, then fill the remaining slots by score
+        let mut ranked_topics: Vec<&String> = topics.keys().collect();
+        ranked_topics.sort_by(|a, b| topics[*b].len().cmp(&topics[*a].len()).then_with(|| a.cmp(b)));
+
+        let mut picked: Vec<usize> = Vec::new();
+        for topic in ranked_topics {
+            if let Some(idx) = (0..scored.len()).find(|i| !picked.contains(i) && scored[*i].2.contains(&topic)) {
+                picked.push(idx);
+            }
+        }
+        for idx in 0..scored.len() {
+            if !picked.contains(&idx) {
+                picked.push(idx);
+            }
+        }
+
+        for idx in picked.into_iter().take(max_solvers) {
+            let (_, key, matched) = &scored[idx];
+            let reason = matched.iter()
+                .map(|t| format!("{} ({})", t, topics[*t].join(", ")))
+                .collect::<Vec<_>>()
+                .join("; ");
+            decision.solvers.push((*key).clone());
+            decision.reasons.push(format!("{}: matches {}", key, reason));
+        }
+
+        if decision.solvers.is_empty() {
+            let mut general: Vec<&String> = candidates.iter()
+                .filter(|(_, tool)| tool.tags.iter().any(|t| t.eq_ignore_ascii_case(GENERAL_TAG)))
+                .map(|(key, _)| key)
+                .collect();
+            if general.is_empty() {
+                general = candidates.iter().map(|(key, _)| key).collect();
+            }
+            general.sort();
+
+            for key in general.into_iter().take(max_solvers) {
+                decision.solvers.push(key.clone());
+                decision.reasons.push(format!("{}: no topic matched, using general-purpose tool", key));
+            }
+        }
+
+        decision
+    }
+
+    /// Asks the router tool for topic tags, keeping only tags known to the rules.
+    pub async fn classify_with_tool(router: &ToolConfig, prompt: &str, rules: &BTreeMap<String, Vec<String>>) -> Result<BTreeMap<String, Vec<String>>> {
+        let known: Vec<&str> = rules.keys().map(|k| k.as_str()).collect();
+        let router_prompt = format!(
+            "Classify the following question into one or more of these topics: {}. Reply with the matching topic names only, comma-separated.\n\nQuestion: {}",
+            known.join(", "),
+            prompt
+        );
+
+        let reply = Validator::strip_ansi_codes(&ToolManager::run_tool(router, &router_prompt).await?).to_lowercase();
+        let topics = known.iter()
+            .filter(|tag| reply.split(|c: char| !c.is_alphanumeric()).any(|w| w == **tag))
+            .map(|tag| (tag.to_string(), vec![format!("suggested by {}", router.name)]))
+            .collect();
+        Ok(topics)
+    }
+
+    /// Picks solvers for a prompt, preferring the router tool when one is configured and available.
+    pub async fn route(prompt: &str, candidates: &[(String, ToolConfig)], routing: &RoutingConfig, router: Option<&ToolConfig>) -> RoutingDecision {
+        let rules = routing.rules();
+
+        let topics = match router {
+            Some(tool) => match Self::classify_with_tool(tool, prompt, &rules).await {
+                Ok(topics) if !topics.is_empty() => topics,
+                Ok(_) => {
+                    warn!("Router {} returned no known topics, using keyword rules", tool.name);
+                    Self::classify(prompt, &rules)
+                },
+                Err(e) => {
+                    warn!("Router {} failed ({}), using keyword rules", tool.name, e);
+                    Self::classify(prompt, &rules)
+                }
+            },
+            None => Self::classify(prompt, &rules),
+        };
+
+        let decision = Self::select(&topics, candidates, routing.max_solvers);
+        info!("Auto-selected solvers: {}", decision.solvers.join(", "));
+        decision
+    }
+}
@@ -0,0 +1,374 @@
+//! Optional per-tool process sandbox (`[tools.<key>.sandbox]`).
+//!
+//! A sandboxed tool runs in its own process group, so a timeout kills everything it
+//! started, with an environment scrubbed down to an allowlist and, by default, a private
+//! temporary working directory. On Linux it can also be confined with Landlock
+//! (writes only to the private directory and listed paths), a seccomp filter that denies
+//! administrative syscalls, and, for local tools, an empty network namespace.
+//! Every requested restriction is enforced or the tool does not run.
+
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use crate::{ToolConfig, CliError, Result, config::expand_home, constants::SANDBOX_BASE_ENV};
+
+/// The `sandbox` table of a tool.
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct SandboxConfig {
+    /// Environment variables passed through on top of `SANDBOX_BASE_ENV`.
+    pub env_allowlist: Vec<String>,
+    /// Run in a fresh temporary directory, also used as `TMPDIR`, removed afterwards.
+    pub private_tmp: bool,
+    /// Restrict writes to the private directory, `/dev` and `writable` (Linux 5.13+).
+    pub landlock: bool,
+    /// Extra paths the tool may write to under Landlock, such as its state directory.
+    pub writable: Vec<String>,
+    /// Deny administrative syscalls such as `ptrace`, `mount` and `bpf`.
+    pub seccomp: bool,
+    /// Run in an empty network namespace; only allowed for local tools.
+    pub isolate_network: bool,
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        SandboxConfig {
+            env_allowlist: Vec::new(),
+            private_tmp: true,
+            landlock: false,
+            writable: Vec::new(),
+            seccomp: false,
+            isolate_network: false,
+        }
+    }
+}
+
+impl SandboxConfig {
+    pub fn validate(&self, key: &str, tool: &ToolConfig) -> Result<()> {
+        if self.isolate_network && !tool.is_local() {
+            return Err(CliError::ConfigError {
+                message: format!("Tool '{}' sets sandbox.isolate_network but is not a local tool", key)
+            });
+        }
+        if let Some(name) = self.env_allowlist.iter().find(|name| name.is_empty() || name.contains(['=', '\0'])) {
+            return Err(CliError::ConfigError {
+                message: format!("Tool '{}' has an invalid sandbox.env_allowlist entry '{}'", key, name)
+            });
+        }
+        Ok(())
+    }
+
+    /// The variables a sandboxed tool sees, taken from `lookup` (normally the current environment).
+    pub fn environment(&self, lookup: impl Fn(&str) -> Option<String>) -> Vec<(String, String)> {
+        let mut names: Vec<&str> = SANDBOX_BASE_ENV.to_vec();
+        names.extend(self.env_allowlist.iter().map(|name| name.as_str()));
+        names.sort();
+        names.dedup();
+        names.into_iter()
+            .filter_map(|name| lookup(name).map(|value| (name.to_string(), value)))
+            .collect()
+    }
+}
+
+/// A directory created with mode 0700 and removed, with its contents, on drop.
+struct PrivateDir(PathBuf);
+
+impl PrivateDir {
+    fn create() -> Result<Self> {
+        use std::hash::{BuildHasher, Hasher};
+
+        let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+        hasher.write_u128(std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_nanos());
+        let path = std::env::temp_dir().join(format!("ai-co-{}-{:016x}", std::process::id(), hasher.finish()));
+
+        let mut builder = std::fs::DirBuilder::new();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        builder.create(&path).map_err(|e| CliError::ConfigError {
+            message: format!("Failed to create private directory {}: {}", path.display(), e)
+        })?;
+        Ok(PrivateDir(path))
+    }
+}
+
+impl Drop for PrivateDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Everything a sandboxed run needs, prepared before the process is spawned and
+/// released (the private directory removed) when dropped after it exits.
+pub struct Sandbox {
+    environment: Vec<(String, String)>,
+    workdir: Option<PrivateDir>,
+    #[cfg(target_os = "linux")]
+    confinement: linux::Confinement,
+}
+
+impl Sandbox {
+    /// Prepares the sandbox for `tool`. Fails when a requested restriction is unavailable.
+    pub fn prepare(tool: &ToolConfig, config: &SandboxConfig) -> Result<Self> {
+        let workdir = if config.private_tmp { Some(PrivateDir::create()?) } else { None };
+        let mut writable: Vec<PathBuf> = workdir.iter().map(|dir| dir.0.clone()).collect();
+        writable.push(PathBuf::from("/dev"));
+        writable.extend(config.writable.iter().map(|path| expand_home(path)));
+
+        #[cfg(target_os = "linux")]
+        let confinement = linux::Confinement::prepare(config, &writable).map_err(|reason| CliError::SandboxError {
+            tool: tool.name.clone(),
+            reason,
+        })?;
+        #[cfg(not(target_os = "linux"))]
+        if config.landlock || config.seccomp || config.isolate_network {
+            return Err(CliError::SandboxError {
+                tool: tool.name.clone(),
+                reason: "Landlock, seccomp and network isolation are only available on Linux".to_string(),
+            });
+        }
+
+        Ok(Sandbox {
+            environment: config.environment(|name| std::env::var(name).ok()),
+            workdir,
+            #[cfg(target_os = "linux")]
+            confinement,
+        })
+    }
+
+    /// The private working directory, if any.
+    pub fn workdir(&self) -> Option<&Path> {
+        self.workdir.as_ref().map(|dir| dir.0.as_path())
+    }
+
+    /// Configures `cmd` to run inside the sandbox.
+    pub fn apply(&self, cmd: &mut tokio::process::Command) {
+        cmd.env_clear();
+        cmd.envs(self.environment.iter().map(|(name, value)| (name, value)));
+        if let Some(dir) = self.workdir() {
+            cmd.current_dir(dir);
+            for name in ["TMPDIR", "TMP", "TEMP"] {
+                cmd.env(name, dir);
+            }
+        }
+
+        #[cfg(unix)]
+        cmd.process_group(0);
+        #[cfg(target_os = "linux")]
+        self.confinement.apply(cmd);
+    }
+
+    /// Kills the process group led by `pid`, including anything the tool left running.
+    pub fn kill_tree(pid: u32) {
+        #[cfg(unix)]
+        unsafe {
+            libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) mod linux {
+    use super::SandboxConfig;
+    use std::os::fd::{AsRawFd, OwnedFd};
+    use std::path::PathBuf;
+
+    const LANDLOCK_CREATE_RULESET_VERSION: libc::c_uint = 1;
+    const LANDLOCK_RULE_PATH_BENEATH: libc::c_int = 1;
+
+    const ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+    const ACCESS_FS_REMOVE_DIR: u64 = 1 << 4;
+    const ACCESS_FS_REMOVE_FILE: u64 = 1 << 5;
+    const ACCESS_FS_MAKE_CHAR: u64 = 1 << 6;
+    const ACCESS_FS_MAKE_DIR: u64 = 1 << 7;
+    const ACCESS_FS_MAKE_REG: u64 = 1 << 8;
+    const ACCESS_FS_MAKE_SOCK: u64 = 1 << 9;
+    const ACCESS_FS_MAKE_FIFO: u64 = 1 << 10;
+    const ACCESS_FS_MAKE_BLOCK: u64 = 1 << 11;
+    const ACCESS_FS_MAKE_SYM: u64 = 1 << 12;
+    const ACCESS_FS_REFER: u64 = 1 << 13;
+    const ACCESS_FS_TRUNCATE: u64 = 1 << 14;
+
+    #[repr(C)]
+    struct RulesetAttr {
+        handled_access_fs: u64,
+    }
+
+    #[repr(C, packed)]
+    struct PathBeneathAttr {
+        allowed_access: u64,
+        parent_fd: i32,
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    const AUDIT_ARCH: u32 = 0xC000_003E;
+    #[cfg(target_arch = "aarch64")]
+    const AUDIT_ARCH: u32 = 0xC000_00B7;
+
+    /// Syscalls a sandboxed tool gets `EPERM` for.
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    const DENIED_SYSCALLS: &[libc::c_long] = &[
+        libc::SYS_ptrace, libc::SYS_process_vm_readv, libc::SYS_process_vm_writev,
+        libc::SYS_mount, libc::SYS_umount2, libc::SYS_pivot_root, libc::SYS_chroot,
+        libc::SYS_setns, libc::SYS_unshare, libc::SYS_open_by_handle_at, libc::SYS_name_to_handle_at,
+        libc::SYS_kexec_load, libc::SYS_init_module, libc::SYS_finit_module, libc::SYS_delete_module,
+        libc::SYS_reboot, libc::SYS_swapon, libc::SYS_swapoff, libc::SYS_acct,
+        libc::SYS_bpf, libc::SYS_perf_event_open, libc::SYS_userfaultfd,
+        libc::SYS_keyctl, libc::SYS_add_key, libc::SYS_request_key,
+        libc::SYS_settimeofday, libc::SYS_clock_settime,
+    ];
+
+    // Offsets into `struct seccomp_data`
+    const SECCOMP_DATA_NR: u32 = 0;
+    const SECCOMP_DATA_ARCH: u32 = 4;
+    const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+
+    fn statement(code: u32, k: u32) -> libc::sock_filter {
+        libc::sock_filter { code: code as u16, jt: 0, jf: 0, k }
+    }
+
+    fn jump(code: u32, k: u32, jt: u8, jf: u8) -> libc::sock_filter {
+        libc::sock_filter { code: code as u16, jt, jf, k }
+    }
+
+    /// The seccomp program: kill on a foreign architecture, `EPERM` for the denied
+    /// syscalls (and x32 ones on x86_64), allow the rest.
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    pub(crate) fn seccomp_filter() -> Result<Vec<libc::sock_filter>, String> {
+        let load = libc::BPF_LD | libc::BPF_W | libc::BPF_ABS;
+        let equals = libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K;
+        let deny = libc::SECCOMP_RET_ERRNO | libc::EPERM as u32;
+
+        let mut filter = vec![
+            statement(load, SECCOMP_DATA_ARCH),
+            jump(equals, AUDIT_ARCH, 1, 0),
+            statement(libc::BPF_RET | libc::BPF_K, libc::SECCOMP_RET_KILL_PROCESS),
+            statement(load, SECCOMP_DATA_NR),
+        ];
+        if cfg!(target_arch = "x86_64") {
+            filter.push(jump(libc::BPF_JMP | libc::BPF_JGE | libc::BPF_K, X32_SYSCALL_BIT, 0, 1));
+            filter.push(statement(libc::BPF_RET | libc::BPF_K, deny));
+        }
+        for &nr in DENIED_SYSCALLS {
+            filter.push(jump(equals, nr as u32, 0, 1));
+            filter.push(statement(libc::BPF_RET | libc::BPF_K, deny));
+        }
+        filter.push(statement(libc::BPF_RET | libc::BPF_K, libc::SECCOMP_RET_ALLOW));
+        Ok(filter)
+    }
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    pub(crate) fn seccomp_filter() -> Result<Vec<libc::sock_filter>, String> {
+        Err("seccomp filtering is only supported on x86_64 and aarch64".to_string())
+    }
+
+    /// The Landlock ABI version of the running kernel, if Landlock is enabled.
+    fn landlock_abi() -> Option<i64> {
+        let abi = unsafe {
+            libc::syscall(libc::SYS_landlock_create_ruleset, std::ptr::null::<RulesetAttr>(), 0usize, LANDLOCK_CREATE_RULESET_VERSION)
+        };
+        (abi >= 1).then_some(abi)
+    }
+
+    struct Landlock {
+        handled: u64,
+        /// Paths opened with `O_PATH`, with the access allowed beneath each.
+        rules: Vec<(OwnedFd, u64)>,
+    }
+
+    impl Landlock {
+        fn prepare(writable: &[PathBuf]) -> Result<Self, String> {
+            let abi = landlock_abi().ok_or("Landlock is not enabled in this kernel")?;
+            let mut handled = ACCESS_FS_WRITE_FILE | ACCESS_FS_REMOVE_DIR | ACCESS_FS_REMOVE_FILE
+                | ACCESS_FS_MAKE_CHAR | ACCESS_FS_MAKE_DIR | ACCESS_FS_MAKE_REG | ACCESS_FS_MAKE_SOCK
+                | ACCESS_FS_MAKE_FIFO | ACCESS_FS_MAKE_BLOCK | ACCESS_FS_MAKE_SYM;
+            if abi >= 2 {
+                handled |= ACCESS_FS_REFER;
+            }
+            if abi >= 3 {
+                handled |= ACCESS_FS_TRUNCATE;
+            }
+
+            let mut rules = Vec::new();
+            for path in writable {
+                // Listed paths that do not exist yet cannot be granted; skip them
+                let Ok(metadata) = std::fs::metadata(path) else {
+                    continue;
+                };
+                let file = std::fs::OpenOptions::new()
+                    .read(true)
+                    .custom_flags(libc::O_PATH | libc::O_CLOEXEC)
+                    .open(path)
+                    .map_err(|e| format!("cannot open {} for Landlock: {}", path.display(), e))?;
+                let allowed = if metadata.is_dir() { handled } else { handled & (ACCESS_FS_WRITE_FILE | ACCESS_FS_TRUNCATE) };
+                rules.push((OwnedFd::from(file), allowed));
+            }
+            Ok(Landlock { handled, rules })
+        }
+    }
+
+    use std::os::unix::fs::OpenOptionsExt;
+
+    /// The Linux-only restrictions, prepared in the parent so the child only makes syscalls.
+    #[derive(Default)]
+    pub(crate) struct Confinement {
+        landlock: Option<Landlock>,
+        seccomp: Option<Vec<libc::sock_filter>>,
+        isolate_network: bool,
+    }
+
+    impl Confinement {
+        pub(crate) fn prepare(config: &SandboxConfig, writable: &[PathBuf]) -> Result<Self, String> {
+            Ok(Confinement {
+                landlock: if config.landlock { Some(Landlock::prepare(writable)?) } else { None },
+                seccomp: if config.seccomp { Some(seccomp_filter()?) } else { None },
+                isolate_network: config.isolate_network,
+            })
+        }
+
+        pub(crate) fn apply(&self, cmd: &mut tokio::process::Command) {
+            if self.landlock.is_none() && self.seccomp.is_none() && !self.isolate_network {
+                return;
+            }
+
+            let isolate_network = self.isolate_network;
+            let landlock = self.landlock.as_ref().map(|l| {
+                (l.handled, l.rules.iter().map(|(fd, allowed)| (fd.as_raw_fd(), *allowed)).collect::<Vec<_>>())
+            });
+            let seccomp = self.seccomp.clone();
+
+            // Runs in the forked child: no allocation, only syscalls on data prepared above
+            unsafe {
+                cmd.pre_exec(move || {
+                    let check = |result: libc::c_long| {
+                        if result < 0 { Err(std::io::Error::last_os_error()) } else { Ok(()) }
+                    };
+
+                    if isolate_network {
+                        check(libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET) as libc::c_long)?;
+                    }
+                    check(libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) as libc::c_long)?;
+
+                    if let Some((handled, rules)) = &landlock {
+                        let attr = RulesetAttr { handled_access_fs: *handled };
+                        let ruleset = libc::syscall(libc::SYS_landlock_create_ruleset, &attr as *const RulesetAttr, std::mem::size_of::<RulesetAttr>(), 0);
+                        check(ruleset)?;
+                        for (fd, allowed) in rules {
+                            let rule = PathBeneathAttr { allowed_access: *allowed, parent_fd: *fd };
+                            check(libc::syscall(libc::SYS_landlock_add_rule, ruleset, LANDLOCK_RULE_PATH_BENEATH, &rule as *const PathBeneathAttr, 0))?;
+                        }
+                        check(libc::syscall(libc::SYS_landlock_restrict_self, ruleset, 0))?;
+                        libc::close(ruleset as libc::c_int);
+                    }
+
+                    if let Some(filter) = &seccomp {
+                        let program = libc::sock_fprog { len: filter.len() as u16, filter: filter.as_ptr() as *mut libc::sock_filter };
+                        check(libc::prctl(libc::PR_SET_SECCOMP, libc::SECCOMP_MODE_FILTER, &program as *const libc::sock_fprog) as libc::c_long)?;
+                    }
+                    Ok(())
+                });
+            }
+        }
+    }
+}
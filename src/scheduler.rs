@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use crate::ToolConfig;
+
+/// Limits how many tools run at once: at most `max_parallel` overall, and one at a time
+/// within each `exclusive_group` (e.g. every model served by the same Ollama).
+#[derive(Clone, Debug, Default)]
+pub struct Scheduler {
+    global: Option<Arc<Semaphore>>,
+    groups: HashMap<String, Arc<Semaphore>>,
+}
+
+/// Permission to run one tool; the slot is freed when this is dropped.
+#[derive(Debug)]
+pub struct Slot {
+    _group: Option<OwnedSemaphorePermit>,
+    _global: Option<OwnedSemaphorePermit>,
+    /// How long the tool waited for the slot.
+    pub queued: Duration,
+}
+
+/// Queue wait and run time of one solver.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SolverTiming {
+    pub solver: String,
+    pub queued_secs: f32,
+    pub run_secs: f32,
+}
+
+impl Scheduler {
+    /// Builds a scheduler for `tools`, normally every configured tool so fallbacks share the
+    /// limits; `None` means no global limit.
+    pub fn new<'a>(max_parallel: Option<usize>, tools: impl IntoIterator<Item = &'a ToolConfig>) -> Self {
+        let groups = tools.into_iter()
+            .filter_map(|tool| tool.exclusive_group.clone())
+            .map(|group| (group, Arc::new(Semaphore::new(1))))
+            .collect();
+        Scheduler {
+            global: max_parallel.map(|limit| Arc::new(Semaphore::new(limit.max(1)))),
+            groups,
+        }
+    }
+
+    /// Waits for a slot for `tool`. The group slot is taken before the global one, so a
+    /// tool waiting on its group does not hold back tools from other groups.
+    pub async fn acquire(&self, tool: &ToolConfig) -> Slot {
+        let start = Instant::now();
+        let group = match tool.exclusive_group.as_ref().and_then(|group| self.groups.get(group)) {
+            Some(semaphore) => semaphore.clone().acquire_owned().await.ok(),
+            None => None,
+        };
+        let global = match &self.global {
+            Some(semaphore) => semaphore.clone().acquire_owned().await.ok(),
+            None => None,
+        };
+        Slot { _group: group, _global: global, queued: start.elapsed() }
+    }
+}
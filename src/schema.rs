@@ -0,0 +1,211 @@
+use serde_json::{json, Value};
+use crate::redaction::BUILTIN_DETECTORS;
+
+const STRATEGIES: &[&str] = &["judge", "concat", "first", "vote"];
+const FORMATS: &[&str] = &["text", "markdown", "json"];
+const PRIVACY: &[&str] = &["local", "cloud"];
+const REDACTION_POLICIES: &[&str] = &["mask", "block", "local-only", "off"];
+
+fn string_array(description: &str) -> Value {
+    json!({ "type": "array", "items": { "type": "string" }, "description": description })
+}
+
+fn tool_schema() -> Value {
+    json!({
+        "type": "object",
+        "description": "An AI command-line tool",
+        "required": ["name", "command", "args", "description"],
+        "additionalProperties": false,
+        "properties": {
+            "name": { "type": "string", "description": "Human-readable name shown in help" },
+            "command": { "type": "string", "description": "Executable to run; must be in the command allowlist" },
+            "args": string_array("Arguments; {prompt} is replaced by the question"),
+            "description": { "type": "string", "description": "What this tool is good at" },
+            "tags": string_array("Capability tags used by --auto routing"),
+            "timeout_secs": { "type": "integer", "minimum": 1, "description": "Per-tool timeout in seconds" },
+            "version_args": string_array("Arguments that print the version, for ai-co doctor"),
+            "privacy": { "enum": PRIVACY, "description": "local if prompts never leave this machine (used by --local-only)" },
+            "local": { "type": "boolean", "description": "Shorthand for privacy = \"local\"" },
+            "sandbox": { "$ref": "#/definitions/sandbox" },
+            "env": {
+                "type": "object",
+                "additionalProperties": { "type": "string" },
+                "description": "Environment variables for the tool; values may use ${VAR} and ${VAR:-default}"
+            },
+            "env_passthrough": string_array("Variables kept when the environment is cleared"),
+            "env_clear": { "type": "boolean", "description": "Start the tool with an empty environment" },
+            "cwd": { "type": "string", "description": "Working directory; ~/ is expanded" },
+            "exclusive_group": { "type": "string", "description": "Tools in the same group run one at a time" },
+            "fallback": string_array("Tools that answer instead when this one is unavailable, fails or hits an auth error"),
+            "hedge_after_secs": { "type": "integer", "minimum": 1, "description": "Start the first fallback alongside this tool after this many seconds" },
+            "max_input_chars": { "type": "integer", "minimum": 2000, "description": "Longest consensus prompt this tool accepts; longer inputs are condensed first" },
+            "weight": { "type": "number", "minimum": 0, "description": "How much this tool's answers count in a vote and for the judge (default 1)" }
+        }
+    })
+}
+
+fn profile_schema() -> Value {
+    json!({
+        "type": "object",
+        "description": "A named solver and consensus combination",
+        "additionalProperties": false,
+        "properties": {
+            "description": { "type": "string" },
+            "solvers": string_array("Tools that answer the question"),
+            "consensus": {
+                "oneOf": [{ "type": "string" }, { "type": "array", "items": { "type": "string" } }],
+                "description": "Tools used by the judge strategy, tried in order (a list or comma-separated string)"
+            },
+            "judges": { "type": "integer", "minimum": 1, "description": "Number of consensus tools that each synthesize the responses" },
+            "reconcile": { "enum": ["judge", "vote"], "description": "How several judges are reconciled" },
+            "weights": {
+                "type": "object",
+                "additionalProperties": { "type": "number", "minimum": 0 },
+                "description": "Tool weights for this profile, overriding each tool's weight"
+            },
+            "weights_from": { "type": "string", "description": "Scores file written by ai-co eval; each tool's weight is scaled by its accuracy" },
+            "strategy": { "enum": STRATEGIES },
+            "timeout_secs": { "type": "integer", "minimum": 1 },
+            "format": { "enum": FORMATS }
+        }
+    })
+}
+
+fn workflow_schema() -> Value {
+    json!({
+        "type": "object",
+        "description": "A multi-stage pipeline run with ai-co run --workflow",
+        "required": ["stages"],
+        "additionalProperties": false,
+        "properties": {
+            "description": { "type": "string" },
+            "stages": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["name", "tools", "prompt"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "name": { "type": "string" },
+                        "tools": string_array("Tools that run this stage"),
+                        "prompt": { "type": "string", "description": "Template with {prompt} and {stages.<name>.responses|output}" },
+                        "aggregate": { "enum": STRATEGIES },
+                        "consensus": { "type": "string", "description": "Tool used for judge aggregation" },
+                        "depends_on": string_array("Stages that must finish first")
+                    }
+                }
+            }
+        }
+    })
+}
+
+fn routing_schema() -> Value {
+    json!({
+        "type": "object",
+        "description": "Settings for --auto solver routing",
+        "additionalProperties": false,
+        "properties": {
+            "max_solvers": { "type": "integer", "minimum": 1 },
+            "router": { "type": "string", "description": "Tool asked to classify the prompt" },
+            "rules": {
+                "type": "object",
+                "description": "Keyword rules keyed by tag",
+                "additionalProperties": string_array("Keywords for this tag")
+            }
+        }
+    })
+}
+
+fn redaction_schema() -> Value {
+    json!({
+        "type": "object",
+        "description": "Detection of secrets and personal data in prompts",
+        "additionalProperties": false,
+        "properties": {
+            "policy": { "enum": REDACTION_POLICIES, "description": "What to do when a prompt contains sensitive data" },
+            "patterns": {
+                "type": "object",
+                "description": "Extra detectors: name to regular expression",
+                "additionalProperties": { "type": "string" }
+            },
+            "disabled": {
+                "type": "array",
+                "items": { "enum": BUILTIN_DETECTORS },
+                "description": "Built-in detectors to turn off"
+            }
+        }
+    })
+}
+
+fn injection_schema() -> Value {
+    json!({
+        "type": "object",
+        "description": "Screening of solver responses for prompt injection",
+        "additionalProperties": false,
+        "properties": {
+            "quarantine": { "type": "boolean", "description": "Leave flagged responses out of the consensus" },
+            "patterns": string_array("Extra case-insensitive regexes that flag a response")
+        }
+    })
+}
+
+fn presentation_schema() -> Value {
+    json!({
+        "type": "object",
+        "description": "How responses are shown to the consensus tool",
+        "additionalProperties": false,
+        "properties": {
+            "shuffle": { "type": "boolean", "description": "Show responses in random order (default true)" },
+            "strip_identity": { "type": "boolean", "description": "Remove phrases in which a model names itself" },
+            "identity_patterns": string_array("Extra case-insensitive regexes for self-identifying phrases"),
+            "attribute": { "type": "boolean", "description": "Have the judge cite the solvers behind each statement" }
+        }
+    })
+}
+
+fn sandbox_schema() -> Value {
+    json!({
+        "type": "object",
+        "description": "Process sandbox for a tool",
+        "additionalProperties": false,
+        "properties": {
+            "env_allowlist": string_array("Environment variables passed through besides PATH, HOME, LANG and the like"),
+            "private_tmp": { "type": "boolean", "description": "Run in a private temporary directory (default true)" },
+            "landlock": { "type": "boolean", "description": "Restrict writes with Landlock (Linux)" },
+            "writable": string_array("Extra paths writable under Landlock"),
+            "seccomp": { "type": "boolean", "description": "Deny administrative syscalls with seccomp (Linux)" },
+            "isolate_network": { "type": "boolean", "description": "Run without network access; local tools only (Linux)" }
+        }
+    })
+}
+
+/// JSON Schema (draft-07) describing `config.toml`, for editor completion and validation.
+pub fn config_schema() -> Value {
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "ai-consensus-cli configuration",
+        "type": "object",
+        "additionalProperties": false,
+        "properties": {
+            "default_profile": { "type": "string", "description": "Profile used when no -s/-c/--profile is given" },
+            "max_parallel": { "type": "integer", "minimum": 1, "description": "Most tools run at once; unlimited when unset" },
+            "tools": { "type": "object", "additionalProperties": { "$ref": "#/definitions/tool" } },
+            "profiles": { "type": "object", "additionalProperties": { "$ref": "#/definitions/profile" } },
+            "workflows": { "type": "object", "additionalProperties": { "$ref": "#/definitions/workflow" } },
+            "routing": { "$ref": "#/definitions/routing" },
+            "redaction": { "$ref": "#/definitions/redaction" },
+            "injection": { "$ref": "#/definitions/injection" },
+            "presentation": { "$ref": "#/definitions/presentation" }
+        },
+        "definitions": {
+            "tool": tool_schema(),
+            "profile": profile_schema(),
+            "workflow": workflow_schema(),
+            "routing": routing_schema(),
+            "redaction": redaction_schema(),
+            "injection": injection_schema(),
+            "presentation": presentation_schema(),
+            "sandbox": sandbox_schema()
+        }
+    })
+}
@@ -0,0 +1,244 @@
+//! Renders captured terminal output to plain text.
+//!
+//! A small VT state machine that drops every control sequence (CSI, OSC, DCS and the
+//! other string sequences, in 7-bit and 8-bit form) and applies the ones that move the
+//! cursor or erase text to a line buffer, so spinner redraws collapse to their final
+//! state. Indentation and spacing are kept as printed.
+
+/// Furthest column a cursor movement can reach; printed text may run past it.
+const MAX_COLUMN: usize = 4096;
+/// Furthest row a cursor movement can reach below the text printed so far.
+const MAX_ROW: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Ground,
+    Escape,
+    /// `ESC` followed by intermediate bytes, waiting for the final byte.
+    EscapeIntermediate,
+    Csi,
+    /// OSC, DCS, SOS, PM or APC: everything up to ST (`ESC \`) or BEL is dropped.
+    String,
+}
+
+struct Screen {
+    lines: Vec<Vec<char>>,
+    row: usize,
+    col: usize,
+    saved: (usize, usize),
+}
+
+impl Screen {
+    fn new() -> Self {
+        Screen { lines: vec![Vec::new()], row: 0, col: 0, saved: (0, 0) }
+    }
+
+    fn line(&mut self) -> &mut Vec<char> {
+        while self.lines.len() <= self.row {
+            self.lines.push(Vec::new());
+        }
+        &mut self.lines[self.row]
+    }
+
+    fn put(&mut self, c: char) {
+        let col = self.col;
+        let line = self.line();
+        while line.len() < col {
+            line.push(' ');
+        }
+        if col < line.len() {
+            line[col] = c;
+        } else {
+            line.push(c);
+        }
+        self.col += 1;
+    }
+
+    /// Moves the cursor, within bounds, so that a hostile sequence such as `ESC[99999999C`
+    /// cannot make the buffer pad out gigabytes.
+    fn move_to(&mut self, row: usize, col: usize) {
+        self.row = row.min(self.lines.len().max(MAX_ROW));
+        self.col = col.min(MAX_COLUMN);
+    }
+
+    fn newline(&mut self) {
+        self.row += 1;
+        self.col = 0;
+        self.line();
+    }
+
+    /// Executes a CSI sequence; `params` are the raw parameter bytes.
+    fn csi(&mut self, params: &str, action: char) {
+        // Private sequences (`ESC[?25l` and friends) only change terminal modes
+        if params.starts_with(['?', '<', '=', '>']) {
+            return;
+        }
+        let args: Vec<usize> = params.split(';').map(|p| p.parse().unwrap_or(0)).collect();
+        let arg = |i: usize| args.get(i).copied().unwrap_or(0);
+        let count = arg(0).max(1);
+
+        let (row, col) = (self.row, self.col);
+        match action {
+            'A' => self.move_to(row.saturating_sub(count), col),
+            'B' => self.move_to(row.saturating_add(count), col),
+            'C' => self.move_to(row, col.saturating_add(count)),
+            'D' => self.move_to(row, col.saturating_sub(count)),
+            'E' => self.move_to(row.saturating_add(count), 0),
+            'F' => self.move_to(row.saturating_sub(count), 0),
+            'G' => self.move_to(row, count - 1),
+            'd' => self.move_to(count - 1, col),
+            'H' | 'f' => self.move_to(arg(0).max(1) - 1, arg(1).max(1) - 1),
+            'K' => {
+                let col = self.col;
+                let line = self.line();
+                match arg(0) {
+                    0 => line.truncate(col),
+                    1 => line.iter_mut().take(col.saturating_add(1)).for_each(|c| *c = ' '),
+                    _ => line.clear(),
+                }
+            },
+            'J' => match arg(0) {
+                0 => {
+                    let col = self.col;
+                    self.line().truncate(col);
+                    self.lines.truncate(self.row.saturating_add(1));
+                },
+                1 => {
+                    for line in self.lines.iter_mut().take(self.row) {
+                        line.clear();
+                    }
+                },
+                _ => {
+                    self.lines = vec![Vec::new()];
+                    self.row = 0;
+                    self.col = 0;
+                },
+            },
+            's' => self.saved = (self.row, self.col),
+            'u' => self.move_to(self.saved.0, self.saved.1),
+            // SGR colours, scrolling regions, mode changes and the rest have no effect on text
+            _ => {},
+        }
+    }
+
+    /// Executes a two-character escape sequence such as `ESC 7`.
+    fn escape(&mut self, c: char) {
+        match c {
+            '7' => self.saved = (self.row, self.col),
+            '8' => (self.row, self.col) = self.saved,
+            'E' => self.newline(),
+            'D' => self.move_to(self.row.saturating_add(1), self.col),
+            'M' => self.row = self.row.saturating_sub(1),
+            _ => {},
+        }
+    }
+
+    fn into_lines(self) -> Vec<String> {
+        self.lines.into_iter()
+            .map(|line| line.into_iter().collect::<String>().trim_end().to_string())
+            .collect()
+    }
+}
+
+fn is_box(c: char) -> bool {
+    ('\u{2500}'..='\u{257F}').contains(&c)
+}
+
+fn is_vertical_border(c: char) -> bool {
+    matches!(c, '│' | '┃' | '║')
+}
+
+/// Removes the frames some CLIs draw around their answer: rule lines made only of
+/// box-drawing characters that start and end with a corner, and a `│` border on both
+/// sides of a line. Tree diagrams (`├── src`) are left alone.
+fn strip_frames(lines: Vec<String>) -> Vec<String> {
+    const LEFT: &[char] = &['┌', '╭', '╔', '┏', '└', '╰', '╚', '┗', '├', '╠', '┣'];
+    const RIGHT: &[char] = &['┐', '╮', '╗', '┓', '┘', '╯', '╝', '┛', '┤', '╣', '┫'];
+
+    lines.into_iter()
+        .filter(|line| {
+            let trimmed = line.trim();
+            let rule = !trimmed.is_empty() && trimmed.chars().all(|c| is_box(c) || c == ' ');
+            !(rule && trimmed.starts_with(LEFT) && trimmed.ends_with(RIGHT))
+        })
+        .map(|line| {
+            let trimmed = line.trim();
+            let mut chars = trimmed.chars();
+            match (chars.next(), chars.next_back()) {
+                (Some(first), Some(last)) if is_vertical_border(first) && is_vertical_border(last) => {
+                    let inner = chars.as_str();
+                    inner.strip_prefix(' ').unwrap_or(inner).trim_end().to_string()
+                },
+                _ => line,
+            }
+        })
+        .collect()
+}
+
+/// Renders terminal output to plain text.
+pub fn render(text: &str) -> String {
+    let mut screen = Screen::new();
+    let mut state = State::Ground;
+    let mut params = String::new();
+
+    for c in text.chars() {
+        match state {
+            State::Ground => match c {
+                '\u{1b}' => state = State::Escape,
+                '\u{9b}' => {
+                    params.clear();
+                    state = State::Csi;
+                },
+                '\u{90}' | '\u{98}' | '\u{9d}' | '\u{9e}' | '\u{9f}' => state = State::String,
+                '\n' => screen.newline(),
+                '\r' => screen.col = 0,
+                '\u{8}' => screen.col = screen.col.saturating_sub(1),
+                '\t' => screen.put('\t'),
+                c if c.is_control() => {},
+                c => screen.put(c),
+            },
+            State::Escape => match c {
+                '[' => {
+                    params.clear();
+                    state = State::Csi;
+                },
+                ']' | 'P' | 'X' | '^' | '_' => state = State::String,
+                '\u{20}'..='\u{2f}' => state = State::EscapeIntermediate,
+                '\u{1b}' => {},
+                c => {
+                    screen.escape(c);
+                    state = State::Ground;
+                },
+            },
+            State::EscapeIntermediate => match c {
+                '\u{20}'..='\u{2f}' => {},
+                '\u{1b}' => state = State::Escape,
+                _ => state = State::Ground,
+            },
+            State::Csi => match c {
+                '\u{30}'..='\u{3f}' => params.push(c),
+                '\u{20}'..='\u{2f}' => {},
+                '\u{40}'..='\u{7e}' => {
+                    screen.csi(&params, c);
+                    state = State::Ground;
+                },
+                '\u{1b}' => state = State::Escape,
+                // Malformed sequence: drop it and keep the character
+                c => {
+                    state = State::Ground;
+                    if !c.is_control() {
+                        screen.put(c);
+                    }
+                },
+            },
+            State::String => match c {
+                '\u{7}' | '\u{9c}' => state = State::Ground,
+                // ESC ends the string; the `\` of ST is then ignored by the Escape state
+                '\u{1b}' => state = State::Escape,
+                _ => {},
+            },
+        }
+    }
+
+    strip_frames(screen.into_lines()).join("\n")
+}
@@ -1,23 +1,116 @@
-use crate::{Config, ToolConfig, ui, Validator, CliError, Result, constants::*};
+use crate::{Config, ToolConfig, ui, progress::{self, Event}, Validator, CliError, Result, Sandbox, Scheduler, SolverTiming, constants::*};
 use log::{info, warn, error};
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
-use tokio::task;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::task::JoinSet;
 
 pub struct ToolManager;
 
+/// The process group of a sandboxed tool, killed when the run is abandoned: timed out,
+/// cancelled, or dropped as the losing attempt of a hedge.
+struct ProcessGroup(Option<u32>);
+
+impl ProcessGroup {
+    /// The tool exited on its own; leaves the group alone.
+    fn release(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        if let Some(pid) = self.0 {
+            Sandbox::kill_tree(pid);
+        }
+    }
+}
+
+/// Tools started by [`ToolManager::run_tool`] that have not exited or been killed yet.
+static RUNNING: AtomicUsize = AtomicUsize::new(0);
+
+/// Counts a tool in [`RUNNING`] until its run ends, however it ends.
+struct Running;
+
+impl Running {
+    fn start() -> Self {
+        RUNNING.fetch_add(1, Ordering::SeqCst);
+        Running
+    }
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        RUNNING.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// A selected solver and the available tools that can answer for it, in order.
+#[derive(Debug, Clone)]
+pub struct SolverSlot {
+    pub solver: String,
+    pub chain: Vec<(String, ToolConfig)>,
+}
+
+/// A solver answered for by a tool from its `fallback` chain.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Substitution {
+    pub solver: String,
+    pub replaced_by: String,
+    pub reason: String,
+}
+
+/// Responses of the slots that answered, and the timing of every tool that ran.
+#[derive(Debug, Clone, Default)]
+pub struct SolverRun {
+    pub responses: Vec<String>,
+    /// The tool behind each response.
+    pub answered: Vec<String>,
+    pub timings: Vec<SolverTiming>,
+    pub substitutions: Vec<Substitution>,
+}
+
+/// The tool that ran, its timing and its response.
+type Attempt = (String, SolverTiming, Result<String>);
+
 impl ToolManager {
     pub fn is_available(tool_config: &ToolConfig) -> bool {
         if Validator::validate_command(&tool_config.command).is_err() {
             return false;
         }
         
-        match std::process::Command::new("which")
-            .arg(&tool_config.command)
-            .output() 
+        Self::resolve_command(&tool_config.command).is_some()
+    }
+
+    /// Resolves a command to the absolute path of an executable, searching `PATH` like a shell would.
+    pub fn resolve_command(command: &str) -> Option<PathBuf> {
+        if command.contains('/') {
+            let path = PathBuf::from(command);
+            return Self::is_executable(&path).then(|| std::fs::canonicalize(&path).unwrap_or(path));
+        }
+        
+        let path_var = std::env::var_os("PATH")?;
+        std::env::split_paths(&path_var)
+            .map(|dir| dir.join(command))
+            .find(|candidate| Self::is_executable(candidate))
+    }
+
+    fn is_executable(path: &Path) -> bool {
+        let Ok(metadata) = std::fs::metadata(path) else {
+            return false;
+        };
+        
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+        }
+        #[cfg(not(unix))]
         {
-            Ok(output) => output.status.success(),
-            Err(_) => false,
+            metadata.is_file()
         }
     }
 
@@ -51,6 +144,33 @@ impl ToolManager {
            .stderr(Stdio::piped())
            .kill_on_drop(true);
         
+        // Kept alive until the tool exits; dropping it removes the private directory
+        let sandbox = match &tool_config.sandbox {
+            Some(sandbox_config) => Some(Sandbox::prepare(tool_config, sandbox_config)?),
+            None => None,
+        };
+        if let Some(sandbox) = &sandbox {
+            sandbox.apply(&mut cmd);
+        } else if tool_config.env_clear {
+            cmd.env_clear();
+        }
+        if sandbox.is_some() || tool_config.env_clear {
+            for name in &tool_config.env_passthrough {
+                if let Some(value) = std::env::var_os(name) {
+                    cmd.env(name, value);
+                }
+            }
+        }
+        cmd.envs(tool_config.environment(&|name| std::env::var(name).ok())?);
+        if let Some(cwd) = tool_config.working_dir() {
+            if !cwd.is_dir() {
+                return Err(CliError::ConfigError { 
+                    message: format!("Working directory {} of tool '{}' does not exist", cwd.display(), tool_config.name)
+                });
+            }
+            cmd.current_dir(cwd);
+        }
+        
         #[cfg(unix)]
         {
             unsafe {
@@ -73,86 +193,272 @@ impl ToolManager {
         
         info!("Running tool: {}", tool_config.name);
         
-        let output = tokio::time::timeout(
-            Duration::from_secs(TOOL_TIMEOUT_SECS),
-            cmd.output()
-        ).await
-        .map_err(|_| CliError::ToolTimeout { 
-            tool: tool_config.name.clone(), 
-            timeout: TOOL_TIMEOUT_SECS 
-        })?
-        .map_err(|e| CliError::ConfigError { 
+        let timeout_secs = tool_config.timeout_secs.unwrap_or(TOOL_TIMEOUT_SECS);
+        let spawn_error = |e: std::io::Error| CliError::ConfigError { 
             message: format!("Failed to execute tool '{}': {}", tool_config.name, e)
-        })?;
+        };
+        // Declared before the child, so it is dropped after the child has been killed
+        let _running = Running::start();
+        let mut child = cmd.spawn().map_err(spawn_error)?;
+        // The child itself is killed on drop; a sandboxed tool's own children go with its group
+        let group = ProcessGroup(sandbox.as_ref().and(child.id()));
+        let (stdout, stderr) = (child.stdout.take(), child.stderr.take());
+        let finished = async {
+            let (stdout, stderr, status) = tokio::join!(Self::read_output(stdout), Self::read_output(stderr), child.wait());
+            status.map(|_| (stdout, stderr))
+        };
+        let cancelled = async {
+            match progress::current_attempt() {
+                Some(id) => progress::cancelled(id).await,
+                None => std::future::pending().await,
+            }
+        };
+        let outcome = tokio::select! {
+            output = tokio::time::timeout(Duration::from_secs(timeout_secs), finished) => match output {
+                Ok(output) => Ok(output.map_err(spawn_error)?),
+                Err(_) => Err(CliError::ToolTimeout { 
+                    tool: tool_config.name.clone(), 
+                    timeout: timeout_secs 
+                }),
+            },
+            _ = cancelled => Err(CliError::ToolCancelled { tool: tool_config.name.clone() }),
+        };
+        let (stdout, stderr) = outcome?;
+        group.release();
         
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stdout = String::from_utf8_lossy(&stdout);
+        let stderr = String::from_utf8_lossy(&stderr);
         let combined_output = format!("{}{}", stdout, stderr);
         
-        Ok(combined_output)
+        // Spinners, colours and hyperlinks must not reach the consensus tool
+        Ok(Validator::strip_ansi_codes(&combined_output))
     }
 
-    pub async fn run_solvers(available_solvers: &[(String, ToolConfig)], prompt: &str) -> Result<Vec<String>> {
-        let solver_count = available_solvers.len();
+    /// Waits until every tool started by this process has exited or been killed, for at most
+    /// `limit`. Returns whether they all did; a run that was aborted calls it before exiting.
+    pub async fn wait_for_tools(limit: Duration) -> bool {
+        let waited = tokio::time::timeout(limit, async {
+            while RUNNING.load(Ordering::SeqCst) > 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        });
+        waited.await.is_ok()
+    }
+
+    /// Reads a stream of a tool to the end, reporting each chunk as progress of the current tool.
+    async fn read_output(stream: Option<impl AsyncRead + Unpin>) -> Vec<u8> {
+        let mut output = Vec::new();
+        let Some(mut stream) = stream else {
+            return output;
+        };
+        let mut buffer = [0u8; 4096];
+        loop {
+            match stream.read(&mut buffer).await {
+                Ok(0) | Err(_) => break,
+                Ok(read) => {
+                    progress::output(&String::from_utf8_lossy(&buffer[..read]));
+                    output.extend_from_slice(&buffer[..read]);
+                },
+            }
+        }
+        output
+    }
+
+    /// Plans one slot per solver: the solver itself when available, then the available tools of
+    /// its `fallback` chain. Fallbacks that are solvers of this run anyway are left out. Returns
+    /// the slots and the solvers with no available tool at all.
+    pub fn plan_slots(solvers: &[String], config: &Config) -> (Vec<SolverSlot>, Vec<String>) {
+        Self::plan_slots_where(solvers, config, Self::is_available)
+    }
+
+    /// [`plan_slots`](Self::plan_slots) with `usable` deciding which tools count as available.
+    pub fn plan_slots_where(solvers: &[String], config: &Config, usable: impl Fn(&ToolConfig) -> bool) -> (Vec<SolverSlot>, Vec<String>) {
+        let mut slots = Vec::new();
+        let mut unavailable = Vec::new();
+        
+        for solver in solvers {
+            let tool = &config.tools[solver];
+            let mut chain = Vec::new();
+            if usable(tool) {
+                chain.push((solver.clone(), tool.clone()));
+            }
+            for fallback in &tool.fallback {
+                if solvers.contains(fallback) || chain.iter().any(|(key, _)| key == fallback) {
+                    continue;
+                }
+                if let Some(fallback_tool) = config.tools.get(fallback).filter(|t| usable(t)) {
+                    chain.push((fallback.clone(), fallback_tool.clone()));
+                }
+            }
+            
+            if chain.is_empty() {
+                unavailable.push(solver.clone());
+            } else {
+                slots.push(SolverSlot { solver: solver.clone(), chain });
+            }
+        }
+        
+        (slots, unavailable)
+    }
+
+    /// Runs one tool in a scheduler slot to answer for `solver`. An authentication error counts
+    /// as a failure, and an attempt cancelled with [`progress::cancel`] fails with
+    /// `ToolCancelled`, queued or running.
+    pub(crate) async fn attempt(solver: String, name: String, tool_config: ToolConfig, prompt: String, scheduler: Scheduler) -> Attempt {
+        let registration = progress::register();
+        let id = registration.id();
+        progress::emit(Event::Queued { id, solver, tool: name.clone() });
+        let cancelled = CliError::ToolCancelled { tool: tool_config.name.clone() };
+        let slot = tokio::select! {
+            slot = scheduler.acquire(&tool_config) => slot,
+            _ = progress::cancelled(id) => {
+                progress::emit(Event::Finished { id, result: Err(cancelled.to_string()) });
+                let timing = SolverTiming { solver: name.clone(), queued_secs: 0.0, run_secs: 0.0 };
+                return (name, timing, Err(cancelled));
+            },
+        };
+        progress::emit(Event::Started { id });
+        let started = std::time::Instant::now();
+        let result = match progress::scope(id, Self::run_tool(&tool_config, &prompt)).await {
+            Ok(response) if Validator::is_authentication_error(&response) => Err(CliError::AuthenticationFailed { 
+                tool: tool_config.name.clone(), 
+                reason: "the tool reported an authentication error".to_string()
+            }),
+            result => result,
+        };
+        let timing = SolverTiming {
+            solver: name.clone(),
+            queued_secs: slot.queued.as_secs_f32(),
+            run_secs: started.elapsed().as_secs_f32(),
+        };
+        info!("Tool {} queued {:.1}s, ran {:.1}s", name, timing.queued_secs, timing.run_secs);
+        let outcome = result.as_ref().map(|response| response.clone()).map_err(|e| e.to_string());
+        progress::emit(Event::Finished { id, result: outcome });
+        (name, timing, result)
+    }
+
+    /// Tries the tools of a slot in order until one answers. When a tool has `hedge_after_secs`
+    /// and is still running after that long, the next tool starts alongside it and the first
+    /// answer wins. A cancelled tool ends the chain. Returns every finished attempt, the answer last.
+    async fn run_chain(solver: String, chain: Vec<(String, ToolConfig)>, prompt: String, scheduler: Scheduler) -> Vec<Attempt> {
+        let mut attempts = Vec::new();
+        let mut next = 0;
+        
+        while next < chain.len() {
+            let (name, tool) = chain[next].clone();
+            let hedge = tool.hedge_after_secs.filter(|_| next + 1 < chain.len());
+            let mut current = Box::pin(Self::attempt(solver.clone(), name.clone(), tool, prompt.clone(), scheduler.clone()));
+            next += 1;
+            
+            let finished = match hedge {
+                None => vec![current.await],
+                Some(secs) => tokio::select! {
+                    done = &mut current => vec![done],
+                    _ = tokio::time::sleep(Duration::from_secs(secs)) => {
+                        let (hedge_name, hedge_tool) = chain[next].clone();
+                        next += 1;
+                        info!("Tool {} still running after {}s, starting {}", name, secs, hedge_name);
+                        let mut hedged = Box::pin(Self::attempt(solver.clone(), hedge_name, hedge_tool, prompt.clone(), scheduler.clone()));
+                        // The slower tool is dropped, which kills it
+                        tokio::select! {
+                            done = &mut current => if done.2.is_ok() { vec![done] } else { vec![done, hedged.await] },
+                            done = &mut hedged => if done.2.is_ok() { vec![done] } else { vec![done, current.await] },
+                        }
+                    },
+                },
+            };
+            
+            let answered = finished.iter().any(|(_, _, result)| result.is_ok());
+            let cancelled = finished.iter().all(|(_, _, result)| matches!(result, Err(CliError::ToolCancelled { .. })));
+            for attempt in finished {
+                if let Err(e) = &attempt.2 {
+                    error!("Tool {} failed: {}", attempt.0, e);
+                }
+                attempts.push(attempt);
+            }
+            if answered || cancelled {
+                break;
+            }
+        }
+        
+        attempts
+    }
+
+    /// Runs the slots concurrently, as far as `scheduler` allows, failing over along each chain.
+    pub async fn run_solvers(slots: &[SolverSlot], prompt: &str, scheduler: &Scheduler) -> Result<SolverRun> {
+        let solver_count = slots.len();
         
         if solver_count == 1 {
-            println!("🤖 Running 1 solver...");
+            ui::show_single_solver_start();
         } else {
             ui::show_progress_start(solver_count);
         }
         
         let start_time = std::time::Instant::now();
         
-        let mut tasks = Vec::new();
-        for (solver_idx, (solver_name, tool_config)) in available_solvers.iter().enumerate() {
-            let tool_config = tool_config.clone();
-            let prompt = prompt.to_string();
-            let solver_name = solver_name.clone();
-            
-            tasks.push(task::spawn(async move {
-                let result = Self::run_tool(&tool_config, &prompt).await;
-                (solver_idx, solver_name, result)
-            }));
+        // Dropping the run aborts the slots, which kills their tools
+        let mut tasks = JoinSet::new();
+        for (index, slot) in slots.iter().enumerate() {
+            let (solver, chain) = (slot.solver.clone(), slot.chain.clone());
+            let (prompt, scheduler) = (prompt.to_string(), scheduler.clone());
+            tasks.spawn(async move { (index, Self::run_chain(solver, chain, prompt, scheduler).await) });
+        }
+        let mut finished: Vec<Vec<Attempt>> = slots.iter().map(|_| Vec::new()).collect();
+        while let Some(joined) = tasks.join_next().await {
+            let (index, attempts) = joined.map_err(|e| CliError::ConfigError { 
+                message: format!("Task join error: {}", e)
+            })?;
+            finished[index] = attempts;
         }
         
-        let mut responses = Vec::new();
+        let mut run = SolverRun::default();
         let mut failed_tools = Vec::new();
         
-        for task in tasks {
-            match task.await.map_err(|e| CliError::ConfigError { 
-                message: format!("Task join error: {}", e)
-            })? {
-                (_solver_idx, solver_name, Ok(response)) => {
-                    if Validator::is_authentication_error(&response) {
-                        ui::show_failure();
-                        warn!("Authentication error for tool: {}", solver_name);
-                        failed_tools.push(solver_name);
-                    } else {
-                        ui::show_success();
-                        info!("Tool {} completed successfully", solver_name);
-                        responses.push(response);
-                    }
+        for (slot, attempts) in slots.iter().zip(finished) {
+            let answer = attempts.last().filter(|(_, _, result)| result.is_ok()).map(|(name, _, _)| name.clone());
+            match &answer {
+                Some(name) => {
+                    ui::show_success();
+                    info!("Tool {} completed successfully", name);
                 },
-                (_solver_idx, solver_name, Err(e)) => {
+                None => {
                     ui::show_failure();
-                    error!("Tool {} failed: {}", solver_name, e);
-                    failed_tools.push(solver_name);
+                    failed_tools.push(slot.solver.clone());
+                },
+            }
+            
+            if let Some(name) = answer.as_ref().filter(|name| **name != slot.solver) {
+                let reason = match attempts.iter().find(|(tool, _, _)| *tool == slot.solver) {
+                    _ if slot.chain[0].0 != slot.solver => "unavailable".to_string(),
+                    Some((_, _, Err(e))) => e.to_string(),
+                    _ => format!("still running after {}s", slot.chain[0].1.hedge_after_secs.unwrap_or_default()),
+                };
+                run.substitutions.push(Substitution { solver: slot.solver.clone(), replaced_by: name.clone(), reason });
+            }
+            
+            for (name, timing, result) in attempts {
+                run.timings.push(timing);
+                if let Ok(response) = result {
+                    run.answered.push(name);
+                    run.responses.push(response);
                 }
             }
         }
         
         let solver_time = start_time.elapsed();
         ui::show_timing(solver_time.as_secs_f32());
+        ui::show_queue_waits(&run.timings);
+        ui::show_substitutions(&run.substitutions);
         
         if !failed_tools.is_empty() {
             warn!("Failed tools: {}", failed_tools.join(", "));
         }
         
-        if responses.is_empty() {
+        if run.responses.is_empty() {
             return Err(CliError::AllSolversFailed);
         }
         
-        info!("Successfully collected {} responses", responses.len());
-        Ok(responses)
+        info!("Successfully collected {} responses", run.responses.len());
+        Ok(run)
     }
 }
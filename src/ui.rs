@@ -1,29 +1,275 @@
+use std::collections::BTreeMap;
 use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::{CliError, attribution::Attribution, batch::RowResult, eval::{CaseResult, Outcome}, redaction::RedactionPolicy, privacy::LocalRun, injection::Flagged, scheduler::SolverTiming, tools::Substitution, consensus::Verdict, presentation::Presentation};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+static BATCH: AtomicBool = AtomicBool::new(false);
+
+/// Suppresses progress output, e.g. when stdout carries JSON.
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Replaces the progress of each prompt with one line per row of a batch.
+pub fn set_batch(batch: bool) {
+    BATCH.store(batch, Ordering::Relaxed);
+}
+
+fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed) || BATCH.load(Ordering::Relaxed)
+}
+
+fn batch_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+pub fn show_single_solver_start() {
+    if quiet() {
+        return;
+    }
+    println!("🤖 Running 1 solver...");
+}
+
+pub fn show_unavailable(tools: &[String]) {
+    if quiet() {
+        return;
+    }
+    println!("❌ Unavailable tools: {} (skipping)", tools.join(", "));
+}
 
 pub fn show_progress_start(count: usize) {
+    if quiet() {
+        return;
+    }
     print!("🤖 Running {} solver(s)... ", count);
     io::stdout().flush().unwrap();
 }
 
 pub fn show_success() {
+    if quiet() {
+        return;
+    }
     print!("✅");
     io::stdout().flush().unwrap();
 }
 
 pub fn show_failure() {
+    if quiet() {
+        return;
+    }
     print!("❌");
     io::stdout().flush().unwrap();
 }
 
 pub fn show_timing(seconds: f32) {
+    if quiet() {
+        return;
+    }
     println!(" ({:.1}s)", seconds);
 }
 
+/// Lists the solvers that waited for a slot (see `max_parallel` and `exclusive_group`).
+pub fn show_queue_waits(timings: &[SolverTiming]) {
+    if quiet() {
+        return;
+    }
+    let waits: Vec<String> = timings.iter()
+        .filter(|t| t.queued_secs >= 0.1)
+        .map(|t| format!("{} waited {:.1}s, ran {:.1}s", t.solver, t.queued_secs, t.run_secs))
+        .collect();
+    if !waits.is_empty() {
+        println!("⏳ Queued: {}", waits.join("; "));
+    }
+}
+
+/// Lists the solvers that a fallback tool answered for.
+pub fn show_substitutions(substitutions: &[Substitution]) {
+    if quiet() {
+        return;
+    }
+    for substitution in substitutions {
+        println!("↪️  {} answered for {} ({})", substitution.replaced_by, substitution.solver, substitution.reason);
+    }
+}
+
 pub fn show_consensus_start() {
+    if quiet() {
+        return;
+    }
     print!("🧠 Getting consensus... ");
     io::stdout().flush().unwrap();
 }
 
 pub fn show_consensus_complete(seconds: f32) {
+    if quiet() {
+        return;
+    }
     println!("✅ ({:.1}s)\n", seconds);
 }
+
+/// Reports consensus tools that failed over and, for a panel, who judged and who reconciled.
+pub fn show_verdict(verdict: &Verdict) {
+    if quiet() {
+        return;
+    }
+    for (tool, reason) in &verdict.failures {
+        println!("↪️  Consensus tool {} failed: {}", tool, reason);
+    }
+    if !verdict.judges.is_empty() {
+        let reconciled = verdict.tool.as_ref().map(|t| format!("reconciled by {}", t)).unwrap_or_else(|| "reconciled by vote".to_string());
+        println!("⚖️  Judges: {} ({})", verdict.judges.join(", "), reconciled);
+    }
+}
+
+pub fn show_presentation(presentation: &Presentation) {
+    if quiet() {
+        return;
+    }
+    if let Some(seed) = presentation.seed {
+        println!("🔀 Responses shuffled for the judge (--seed {})", seed);
+    }
+    if presentation.stripped > 0 {
+        println!("🕶️  Removed {} self-identifying phrase(s) before judging", presentation.stripped);
+    }
+}
+
+pub fn show_weights(weights: &BTreeMap<String, f64>) {
+    if quiet() || weights.is_empty() {
+        return;
+    }
+    println!("🏋️  Weights: {}", weights.iter().map(|(tool, weight)| format!("{} {}", tool, weight)).collect::<Vec<_>>().join(", "));
+}
+
+pub fn show_attribution(attribution: &Attribution) {
+    if quiet() {
+        return;
+    }
+    if !attribution.invalid.is_empty() {
+        println!("⚠️  The judge cited responses that do not exist: {}", attribution.invalid.join(", "));
+    }
+    let uncited = attribution.uncited();
+    if uncited > 0 {
+        println!("🔎 {} of {} statement(s) cite no response", uncited, attribution.claims.len());
+    }
+}
+
+pub fn show_eval_start(pending: usize, done: usize) {
+    if quiet() {
+        return;
+    }
+    if done > 0 {
+        println!("🧪 Evaluating {} case(s), {} already scored", pending, done);
+    } else {
+        println!("🧪 Evaluating {} case(s)", pending);
+    }
+}
+
+pub fn show_eval_case(count: usize, total: usize, result: &CaseResult) {
+    if quiet() {
+        return;
+    }
+    let marks = |outcomes: &[Outcome]| outcomes.iter()
+        .map(|o| format!("{} {}", o.name, if o.failed { "⚠️" } else if o.correct { "✅" } else { "❌" }))
+        .collect::<Vec<_>>()
+        .join("  ");
+    println!("[{}/{}] {}: {} | {}", count, total, result.id, marks(&result.tools), marks(&result.strategies));
+}
+
+pub fn show_eval_done(scores: &str) {
+    if quiet() {
+        return;
+    }
+    println!("\n📊 Leaderboard saved to {} (use it as weights_from)\n", scores);
+}
+
+pub fn show_batch_start(pending: usize, resumed: usize) {
+    if batch_quiet() {
+        return;
+    }
+    if resumed > 0 {
+        println!("📦 Answering {} prompt(s), {} already answered", pending, resumed);
+    } else {
+        println!("📦 Answering {} prompt(s)", pending);
+    }
+}
+
+pub fn show_batch_row(count: usize, total: usize, result: &RowResult) {
+    if batch_quiet() {
+        return;
+    }
+    match result {
+        RowResult::Answered { id, report } => match &report.consensus_tool {
+            Some(tool) => println!("[{}/{}] {}: ✅ {} by {}", count, total, id, report.strategy, tool),
+            None => println!("[{}/{}] {}: ✅ {}", count, total, id, report.strategy),
+        },
+        RowResult::Failed { id, error, .. } => println!("[{}/{}] {}: ❌ {}", count, total, id, error),
+    }
+}
+
+pub fn show_batch_done(out: &str) {
+    if batch_quiet() {
+        return;
+    }
+    println!("\n💾 Results saved to {}\n", out);
+}
+
+pub fn show_consensus_failed(error: &CliError) {
+    if quiet() {
+        return;
+    }
+    println!("⚠️  {}; showing all responses instead", error);
+}
+
+pub fn show_stage_start(name: &str, step: usize, total: usize) {
+    if quiet() {
+        return;
+    }
+    println!("▶ Stage {}/{}: {}", step, total, name);
+}
+
+pub fn show_routing(solvers: &[String], topics: &[String], reasons: &[String]) {
+    if quiet() {
+        return;
+    }
+    let topics = if topics.is_empty() { "none".to_string() } else { topics.join(", ") };
+    println!("🧭 Auto-selected solvers: {} (topics: {})", solvers.join(", "), topics);
+    for reason in reasons {
+        println!("   • {}", reason);
+    }
+}
+
+pub fn show_redactions(policy: RedactionPolicy, kinds: &[String]) {
+    if quiet() {
+        return;
+    }
+    let kinds = kinds.join(", ");
+    match policy {
+        RedactionPolicy::Mask => println!("🔒 Masked sensitive data before sending: {}", kinds),
+        RedactionPolicy::LocalOnly => println!("🔒 Sensitive data found ({}), using local tools only", kinds),
+        RedactionPolicy::Off => println!("⚠️  Sensitive data found ({}) but redaction is off", kinds),
+        RedactionPolicy::Block => {},
+    }
+}
+
+pub fn show_local_only(run: &LocalRun) {
+    if quiet() {
+        return;
+    }
+    if !run.dropped.is_empty() {
+        println!("🏠 Local only: skipping cloud tools {}", run.dropped.join(", "));
+    }
+    if !run.replaced_consensus.is_empty() && !run.consensus.is_empty() {
+        println!("🏠 Local only: {} provides consensus instead of {}", run.consensus.join(", "), run.replaced_consensus.join(", "));
+    }
+}
+
+pub fn show_flagged(flagged: &[Flagged]) {
+    if quiet() {
+        return;
+    }
+    for flag in flagged {
+        let action = if flag.quarantined { "left out of the consensus" } else { "kept, use --quarantine to leave it out" };
+        println!("⚠️  Response {} looks like a prompt injection ({}), {}", flag.response, flag.matches.join("; "), action);
+    }
+}
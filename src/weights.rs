@@ -0,0 +1,64 @@
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use crate::{Config, CliError, Result, ProfileConfig, config::expand_home};
+
+/// The part of an `ai-co eval` scores file used for weighting; other fields are ignored.
+#[derive(Deserialize)]
+struct Scores {
+    tools: Vec<Score>,
+}
+
+#[derive(Deserialize)]
+struct Score {
+    tool: String,
+    /// Share of the evaluation prompts the tool answered correctly, from 0 to 1.
+    accuracy: f64,
+}
+
+/// Reads the accuracy of each tool from a scores file such as `{"tools": [{"tool": "q", "accuracy": 0.8}]}`.
+pub fn load_scores(path: &str) -> Result<HashMap<String, f64>> {
+    let file = expand_home(path);
+    let content = std::fs::read_to_string(&file).map_err(|e| CliError::ConfigError {
+        message: format!("Cannot read scores file {}: {}", file.display(), e)
+    })?;
+    let scores: Scores = serde_json::from_str(&content).map_err(|e| CliError::ConfigError {
+        message: format!("Scores file {} is not valid: {}", file.display(), e)
+    })?;
+    let mut accuracy = HashMap::new();
+    for score in scores.tools {
+        if !(0.0..=1.0).contains(&score.accuracy) {
+            return Err(CliError::ConfigError {
+                message: format!("Scores file {} gives tool '{}' an accuracy outside 0..1", file.display(), score.tool)
+            });
+        }
+        accuracy.insert(score.tool, score.accuracy);
+    }
+    Ok(accuracy)
+}
+
+/// The weight of every configured tool: the profile's `weights` entry, else the tool's own
+/// `weight`, else 1. With `weights_from`, it is scaled by the tool's measured accuracy; tools
+/// missing from the scores file get the average accuracy of those present. Weights are
+/// rounded to three decimals.
+pub fn resolve(config: &Config, settings: &ProfileConfig) -> Result<HashMap<String, f64>> {
+    let scores = settings.weights_from.as_deref().map(load_scores).transpose()?.unwrap_or_default();
+    let average = if scores.is_empty() { 1.0 } else { scores.values().sum::<f64>() / scores.len() as f64 };
+    Ok(config.tools.iter()
+        .map(|(key, tool)| {
+            let weight = settings.weights.get(key).copied().unwrap_or_else(|| tool.weight());
+            let scaled = weight * scores.get(key).copied().unwrap_or(average);
+            (key.clone(), (scaled * 1000.0).round() / 1000.0)
+        })
+        .collect())
+}
+
+/// The weights of `tools`, or nothing when they all weigh the same and weighting changes nothing.
+pub fn effective(weights: &HashMap<String, f64>, tools: &[String]) -> BTreeMap<String, f64> {
+    let weight = |tool: &String| weights.get(tool).copied().unwrap_or(1.0);
+    match tools.first() {
+        Some(first) if tools.iter().any(|tool| weight(tool) != weight(first)) => {
+            tools.iter().map(|tool| (tool.clone(), weight(tool))).collect()
+        },
+        _ => BTreeMap::new(),
+    }
+}
@@ -0,0 +1,240 @@
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use crate::{Config, ToolConfig, ToolManager, Scheduler, ConsensusEngine, CliError, Result, ui, consensus::Strategy, injection};
+use lazy_static::lazy_static;
+use log::{info, warn};
+
+lazy_static! {
+    static ref STAGE_REF_REGEX: regex::Regex =
+        regex::Regex::new(r"\{stages\.([A-Za-z0-9_-]+)\.(responses|output)\}").unwrap();
+    static ref PLACEHOLDER_REGEX: regex::Regex =
+        regex::Regex::new(r"\{prompt\}|\{stages\.([A-Za-z0-9_-]+)\.(responses|output)\}").unwrap();
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct WorkflowConfig {
+    #[serde(default)]
+    pub description: String,
+    pub stages: Vec<StageConfig>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct StageConfig {
+    pub name: String,
+    pub tools: Vec<String>,
+    pub prompt: String,
+    #[serde(default)]
+    pub aggregate: Strategy,
+    /// Tool used to synthesize the stage output when `aggregate = "judge"`.
+    pub consensus: Option<String>,
+    /// Stages that must finish first. Defaults to the previous stage when omitted.
+    pub depends_on: Option<Vec<String>>,
+}
+
+/// Output of a completed stage, available to later prompt templates.
+#[derive(Debug, Clone, Default)]
+pub struct StageOutput {
+    pub responses: Vec<String>,
+    pub output: String,
+}
+
+impl StageConfig {
+    fn dependencies(&self, previous: Option<&StageConfig>) -> Vec<String> {
+        match &self.depends_on {
+            Some(deps) => deps.clone(),
+            None => previous.map(|s| vec![s.name.clone()]).unwrap_or_default(),
+        }
+    }
+
+    /// Names of the stages referenced through `{stages.<name>.*}` in the prompt template.
+    pub fn referenced_stages(&self) -> Vec<String> {
+        STAGE_REF_REGEX.captures_iter(&self.prompt)
+            .map(|c| c[1].to_string())
+            .collect()
+    }
+}
+
+impl WorkflowConfig {
+    pub fn validate(&self, name: &str, tools: &HashMap<String, ToolConfig>) -> Result<()> {
+        if self.stages.is_empty() {
+            return Err(CliError::ConfigError {
+                message: format!("Workflow '{}' has no stages", name)
+            });
+        }
+
+        let mut seen = HashSet::new();
+        for stage in &self.stages {
+            if !seen.insert(stage.name.as_str()) {
+                return Err(CliError::ConfigError {
+                    message: format!("Workflow '{}' has duplicate stage '{}'", name, stage.name)
+                });
+            }
+            if stage.tools.is_empty() {
+                return Err(CliError::ConfigError {
+                    message: format!("Stage '{}.{}' has no tools", name, stage.name)
+                });
+            }
+            for tool in stage.tools.iter().chain(stage.consensus.iter()) {
+                if !tools.contains_key(tool) {
+                    return Err(CliError::ToolNotFound { tool: tool.clone() });
+                }
+            }
+            if stage.aggregate == Strategy::Judge && stage.consensus.is_none() {
+                return Err(CliError::ConfigError {
+                    message: format!("Stage '{}.{}' uses judge aggregation but sets no consensus tool", name, stage.name)
+                });
+            }
+        }
+
+        self.execution_order(name)?;
+
+        for stage in &self.stages {
+            for referenced in stage.referenced_stages() {
+                if !self.stages.iter().any(|s| s.name == referenced) {
+                    return Err(CliError::ConfigError {
+                        message: format!("Stage '{}.{}' references unknown stage '{}'", name, stage.name, referenced)
+                    });
+                }
+                if !self.is_upstream(&referenced, &stage.name) {
+                    return Err(CliError::ConfigError {
+                        message: format!("Stage '{}.{}' references '{}' which is not one of its dependencies", name, stage.name, referenced)
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn dependency_map(&self) -> HashMap<&str, Vec<String>> {
+        self.stages.iter().enumerate()
+            .map(|(i, stage)| {
+                let previous = if i > 0 { self.stages.get(i - 1) } else { None };
+                (stage.name.as_str(), stage.dependencies(previous))
+            })
+            .collect()
+    }
+
+    /// Returns true if `upstream` is a direct or transitive dependency of `stage`.
+    fn is_upstream(&self, upstream: &str, stage: &str) -> bool {
+        let deps = self.dependency_map();
+        let mut stack = vec![stage.to_string()];
+        let mut visited = HashSet::new();
+
+        while let Some(current) = stack.pop() {
+            for dep in deps.get(current.as_str()).into_iter().flatten() {
+                if dep == upstream {
+                    return true;
+                }
+                if visited.insert(dep.clone()) {
+                    stack.push(dep.clone());
+                }
+            }
+        }
+        false
+    }
+
+    /// Topologically sorts the stages, keeping declaration order among independent stages.
+    pub fn execution_order(&self, name: &str) -> Result<Vec<usize>> {
+        let deps = self.dependency_map();
+        let mut done: HashSet<&str> = HashSet::new();
+        let mut order = Vec::new();
+
+        for stage in &self.stages {
+            for dep in &deps[stage.name.as_str()] {
+                if !self.stages.iter().any(|s| &s.name == dep) {
+                    return Err(CliError::ConfigError {
+                        message: format!("Stage '{}.{}' depends on unknown stage '{}'", name, stage.name, dep)
+                    });
+                }
+            }
+        }
+
+        while order.len() < self.stages.len() {
+            let next = self.stages.iter().enumerate().find(|(_, stage)| {
+                !done.contains(stage.name.as_str())
+                    && deps[stage.name.as_str()].iter().all(|d| done.contains(d.as_str()))
+            });
+
+            match next {
+                Some((idx, stage)) => {
+                    done.insert(stage.name.as_str());
+                    order.push(idx);
+                },
+                None => {
+                    return Err(CliError::ConfigError {
+                        message: format!("Workflow '{}' has a dependency cycle", name)
+                    });
+                }
+            }
+        }
+
+        Ok(order)
+    }
+}
+
+/// Substitutes `{prompt}` and `{stages.<name>.responses|output}` in a stage template, in one
+/// pass, so placeholders inside the prompt or a stage's output are left as they are.
+pub fn render_template(template: &str, prompt: &str, outputs: &HashMap<String, StageOutput>) -> String {
+    PLACEHOLDER_REGEX.replace_all(template, |caps: &regex::Captures| {
+        let Some(name) = caps.get(1) else {
+            return prompt.to_string();
+        };
+        match outputs.get(name.as_str()) {
+            Some(stage) if &caps[2] == "responses" => stage.responses.iter().enumerate()
+                .map(|(i, r)| format!("Response {}: {}", i + 1, r.trim()))
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+            Some(stage) => stage.output.trim().to_string(),
+            None => String::new(),
+        }
+    }).into_owned()
+}
+
+pub struct WorkflowRunner;
+
+impl WorkflowRunner {
+    /// Runs every stage of a workflow and returns the output of the last stage executed.
+    pub async fn run(config: &Config, workflow_name: &str, prompt: &str) -> Result<String> {
+        let workflow = config.workflows.get(workflow_name).ok_or_else(|| CliError::ConfigError {
+            message: format!("Workflow '{}' not found in configuration", workflow_name)
+        })?;
+
+        let order = workflow.execution_order(workflow_name)?;
+        let mut outputs: HashMap<String, StageOutput> = HashMap::new();
+        let mut last_output = String::new();
+
+        for (step, &idx) in order.iter().enumerate() {
+            let stage = &workflow.stages[idx];
+            ui::show_stage_start(&stage.name, step + 1, order.len());
+
+            let stage_prompt = render_template(&stage.prompt, prompt, &outputs);
+            let (slots, unavailable) = ToolManager::plan_slots(&stage.tools, config);
+
+            if !unavailable.is_empty() {
+                warn!("Stage {}: unavailable tools: {}", stage.name, unavailable.join(", "));
+                ui::show_unavailable(&unavailable);
+            }
+            if slots.is_empty() {
+                return Err(CliError::AllSolversFailed);
+            }
+
+            let scheduler = Scheduler::new(config.max_parallel, config.tools.values());
+            let responses = ToolManager::run_solvers(&slots, &stage_prompt, &scheduler).await?.responses;
+            let screened = injection::screen(&responses, &config.injection, config.injection.quarantine)?;
+            if !screened.flagged.is_empty() {
+                warn!("Stage {}: {} response(s) flagged as possible prompt injection", stage.name, screened.flagged.len());
+                ui::show_flagged(&screened.flagged);
+            }
+            let responses = screened.kept;
+            let judge = stage.consensus.as_ref().map(|c| &config.tools[c]);
+            let output = ConsensusEngine::aggregate(stage.aggregate, judge, responses.clone(), &[], &stage_prompt).await?;
+
+            info!("Stage {} completed with {} responses", stage.name, responses.len());
+            last_output = output.clone();
+            outputs.insert(stage.name.clone(), StageOutput { responses, output });
+        }
+
+        Ok(last_output)
+    }
+}
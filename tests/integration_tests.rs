@@ -152,3 +152,265 @@ fn test_cli_config_file() {
         output.status.success()
     );
 }
+
+#[test]
+fn test_cli_unknown_workflow() {
+    let output = run_cli(&["run", "--", "run", "--workflow", "missing", "-p", "test"]);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Workflow 'missing' not found"));
+}
+
+#[test]
+fn test_cli_help_lists_workflows() {
+    let output = run_cli(&["run", "--", "-s", "", "-c", "", "-p", ""]);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Workflows:"));
+    assert!(stdout.contains("review"));
+}
+
+#[test]
+fn test_cli_unknown_profile() {
+    let output = run_cli(&["run", "--", "--profile", "missing", "-p", "test"]);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Profile 'missing' not found"));
+}
+
+#[test]
+fn test_cli_requires_solvers_without_profile() {
+    let output = run_cli(&["run", "--", "-c", "q", "-p", "test"]);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("No solvers given"));
+}
+
+#[test]
+fn test_cli_config_show_origin() {
+    let output = run_cli(&["run", "--", "config", "show", "--origin", "-c", "claude"]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("tools.q.command = \"q\""));
+    assert!(stdout.contains("# file ("));
+    assert!(stdout.contains("consensus = claude"));
+    assert!(stdout.contains("# cli (--consensus)"));
+}
+
+#[test]
+fn test_cli_doctor_unknown_tool() {
+    let output = run_cli(&["run", "--", "doctor", "invalid-tool", "--no-probe"]);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("not found"));
+}
+
+#[test]
+fn test_cli_config_check() {
+    let output = run_cli(&["run", "--", "config", "check", "config.toml"]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("No problems found"));
+}
+
+#[test]
+fn test_cli_config_schema() {
+    let output = run_cli(&["run", "--", "config", "schema"]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("\"$schema\""));
+    assert!(stdout.contains("\"tools\""));
+}
+
+#[test]
+fn test_cli_init_writes_config() {
+    let dir = std::env::temp_dir().join(format!("ai-co-init-{}", std::process::id()));
+    let path = dir.join("config.toml");
+    let path = path.to_str().unwrap();
+
+    let output = run_cli(&["run", "--", "init", "--yes", "--output", path]);
+    if !output.status.success() {
+        // No supported AI CLI is installed on this machine
+        assert!(String::from_utf8_lossy(&output.stderr).contains("were found on PATH"));
+        return;
+    }
+    let content = std::fs::read_to_string(path).unwrap();
+    assert!(content.contains("generated by `ai-co init`"));
+
+    let output = run_cli(&["run", "--", "config", "check", path]);
+    assert!(output.status.success());
+
+    // Refuses to overwrite without --force
+    let output = run_cli(&["run", "--", "init", "--yes", "--output", path]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("already exists"));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_cli_local_only_fails_closed() {
+    let output = run_cli(&["run", "--", "--local-only", "-s", "q,gemini", "-c", "claude", "-p", "test"]);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Local-only run cannot proceed"));
+}
+
+#[test]
+fn test_cli_rejects_zero_max_parallel() {
+    let output = run_cli(&["run", "--", "--max-parallel", "0", "-s", "q", "-c", "claude", "-p", "test"]);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--max-parallel"));
+}
+
+#[test]
+fn test_cli_attribute_needs_judge() {
+    let output = run_cli(&["run", "--", "--attribute", "--strategy", "vote", "-s", "q,gemini", "-p", "test"]);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--attribute needs the judge strategy"));
+}
+
+#[test]
+fn test_cli_rejects_missing_scores_file() {
+    let output = run_cli(&["run", "--", "--weights-from", "/nonexistent/scores.json", "-s", "q,gemini", "--strategy", "vote", "-p", "test"]);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Cannot read scores file"));
+}
+
+#[test]
+fn test_cli_batch_records_failed_rows() {
+    let dir = std::env::temp_dir().join(format!("ai-co-batch-cli-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("prompts.jsonl");
+    std::fs::write(&input, "{\"id\": \"blank\", \"prompt\": \" \"}\n").unwrap();
+    let input = input.to_string_lossy().to_string();
+    let output = run_cli(&["run", "--", "batch", &input, "-s", "q", "--strategy", "vote"]);
+
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("1 failed (blank)"));
+    let results = std::fs::read_to_string(dir.join("prompts.results.jsonl")).unwrap();
+    assert!(results.contains("\"error\""));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cli_dry_run() {
+    use std::os::unix::fs::PermissionsExt;
+    // Only q is installed; the plan is shown all the same
+    let dir = std::env::temp_dir().join(format!("ai-co-dry-run-cli-{}", std::process::id()));
+    let bin = dir.join("bin");
+    std::fs::create_dir_all(&bin).unwrap();
+    let stub = bin.join("q");
+    std::fs::write(&stub, "#!/bin/sh\necho stub\n").unwrap();
+    std::fs::set_permissions(&stub, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let config = dir.join("config.toml");
+    let tool = |(key, tag): (&str, &str)| format!("[tools.{key}]\nname = \"{key}\"\ncommand = \"{key}\"\nargs = [\"{{prompt}}\"]\ndescription = \"dry run test\"\ntags = [\"{tag}\"]\n\n");
+    std::fs::write(&config, [("q", "general"), ("gemini", "code"), ("claude", "reasoning")].map(tool).concat()).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_ai-co"))
+        .args(["-s", "q,gemini", "-c", "claude", "--seed", "7", "--dry-run", "-p", "What is 2+2?"])
+        .env("AI_CONSENSUS_CONFIG", &config)
+        .env("PATH", &bin)
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(stdout.contains("no tool was started"));
+    assert!(stdout.contains("▶ solver: q"));
+    assert!(stdout.contains("▶ solver: gemini"));
+    assert!(stdout.contains("▶ judge: claude"));
+    assert!(stdout.contains("(response of gemini)"));
+    assert!(stdout.contains("pass --seed 7"));
+    assert!(stdout.contains("a real run would skip: gemini, claude"));
+    assert!(!stdout.contains("🤖 Running"));
+
+    // --auto picks among the tools that are not installed too; outside the repo, whose config.toml has more tools
+    let output = Command::new(env!("CARGO_BIN_EXE_ai-co"))
+        .args(["--auto", "-c", "claude", "--dry-run", "-p", "Why does this rust function not compile?"])
+        .env("AI_CONSENSUS_CONFIG", &config)
+        .env("PATH", &bin)
+        .current_dir(&dir)
+        .output()
+        .expect("Failed to execute command");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(stdout.contains("gemini: matches code"));
+    assert!(stdout.contains("▶ solver: gemini"));
+    assert!(stdout.contains("a real run would skip: gemini, claude"));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cli_eval_local_only() {
+    use std::os::unix::fs::PermissionsExt;
+    // q is a cloud tool that leaves a mark when it runs; ollama is local
+    let dir = std::env::temp_dir().join(format!("ai-co-eval-cli-{}", std::process::id()));
+    let bin = dir.join("bin");
+    std::fs::create_dir_all(&bin).unwrap();
+    let mark = dir.join("q-ran");
+    for (key, script) in [("q", format!("echo \"$1\" > {}\necho hi\n", mark.display())), ("ollama", "echo hi\n".to_string())] {
+        let stub = bin.join(key);
+        std::fs::write(&stub, format!("#!/bin/sh\n{}", script)).unwrap();
+        std::fs::set_permissions(&stub, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    let config = dir.join("config.toml");
+    let tool = |key: &str, local: bool| format!("[tools.{key}]\nname = \"{key}\"\ncommand = \"{key}\"\nargs = [\"{{prompt}}\"]\ndescription = \"eval test\"\nlocal = {local}\n\n");
+    std::fs::write(&config, tool("q", false) + &tool("ollama", true)).unwrap();
+    let dataset = dir.join("cases.jsonl");
+    std::fs::write(&dataset, "{\"prompt\": \"chat hi\", \"expected\": \"hi\"}\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_ai-co"))
+        .args(["eval", &dataset.to_string_lossy(), "-s", "q,ollama", "--strategies", "first", "--local-only"])
+        .env("AI_CONSENSUS_CONFIG", &config)
+        .env("PATH", &bin)
+        .current_dir(&dir)
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let scores: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(dir.join("cases.scores.json")).unwrap()).unwrap();
+    assert_eq!(scores["tools"].as_array().unwrap().iter().map(|t| t["tool"].as_str().unwrap()).collect::<Vec<_>>(), vec!["ollama"]);
+    assert!(!mark.exists(), "the cloud tool was sent the case");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_cli_show_diff() {
+    let dir = std::env::temp_dir().join(format!("ai-co-show-cli-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let run = dir.join("run.json");
+    std::fs::write(&run, r#"{"prompt": "Capital?", "consensus": "Paris", "responses": ["Paris\nFrance", "Lyon\nFrance"], "answered": ["q", "gemini"]}"#).unwrap();
+    let run = run.to_string_lossy().to_string();
+
+    let output = run_cli(&["run", "--", "show", &run, "--diff", "q,gemini"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("--- response 1 (q)\n+++ response 2 (gemini)\n@@ -1,2 +1,2 @@\n-Paris\n+Lyon\n France"));
+
+    let output = run_cli(&["run", "--", "show", &run, "--diff", "gemini,consensus", "--diff-by", "words"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("[-Lyon\nFrance-]{+Paris+}"));
+
+    let output = run_cli(&["run", "--", "show", &run, "--diff", "q,codex"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No response 'codex'"));
+    let _ = std::fs::remove_dir_all(&dir);
+}